@@ -27,6 +27,7 @@ pub mod outline;
 pub mod pattern;
 pub mod render_target;
 pub mod segment;
+pub mod shading;
 pub mod stroke;
 pub mod transform;
 
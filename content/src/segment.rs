@@ -10,6 +10,7 @@
 
 //! Line or curve segments, optimized with SIMD.
 
+use arrayvec::ArrayVec;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::util::{self, EPSILON};
@@ -18,6 +19,8 @@ use pathfinder_simd::default::F32x4;
 use std::f32::consts::SQRT_2;
 
 const MAX_NEWTON_ITERATIONS: u32 = 32;
+const CUBIC_EPSILON: f64 = 1e-9;
+const TANGENT_EPSILON: f64 = 1e-6;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Segment {
@@ -270,6 +273,20 @@ impl<'s> CubicSegment<'s> {
         uv[0] + uv[1] <= 16.0 * tolerance * tolerance
     }
 
+    // Returns the number of line segments needed to flatten this curve, split at uniform
+    // intervals of t, so that no point deviates from the curve by more than `tolerance`. The
+    // count comes from the bound on the curve's second derivative (Wang's formula), without
+    // subdividing, so it may exceed what adaptive flattening produces.
+    pub fn flattening_segment_count(self, tolerance: f32) -> u32 {
+        debug_assert!(tolerance > 0.0);
+        let (p0, p3) = (self.0.baseline.from(), self.0.baseline.to());
+        let (p1, p2) = (self.0.ctrl.from(), self.0.ctrl.to());
+        let max_second_derivative = 6.0 * f32::max((p0 - p1 * 2.0 + p2).length(),
+                                                   (p1 - p2 * 2.0 + p3).length());
+        let count = f32::sqrt(max_second_derivative / (8.0 * tolerance)).ceil();
+        u32::max(count as u32, 1)
+    }
+
     #[inline]
     pub fn split(self, t: f32) -> (Segment, Segment) {
         let (baseline0, ctrl0, baseline1, ctrl1);
@@ -407,6 +424,44 @@ impl<'s> CubicSegment<'s> {
         };
     }
 
+    // Returns the points where this curve crosses `segment`, in order along the curve. The
+    // implicit equation of the segment's line, from `LineSegment2F::line_coords()`, is
+    // substituted into the curve's polynomial form, and each root of the resulting cubic in t is
+    // kept if it lies on both the curve and the segment.
+    pub fn intersect_line(self, segment: LineSegment2F) -> ArrayVec<[Vector2F; 3]> {
+        let mut points = ArrayVec::new();
+        if segment.is_zero_length() {
+            return points;
+        }
+
+        // Signed distances of the control points from the line, scaled by the segment's length.
+        let line = segment.line_coords();
+        let distance = |point: Vector2F| line.x() * point.x() + line.y() * point.y() + line.z();
+        let (d0, d3) = (distance(self.0.baseline.from()), distance(self.0.baseline.to()));
+        let (d1, d2) = (distance(self.0.ctrl.from()), distance(self.0.ctrl.to()));
+
+        // Convert from the Bernstein basis to the power basis.
+        let a = -d0 + 3.0 * (d1 - d2) + d3;
+        let b = 3.0 * (d0 - 2.0 * d1 + d2);
+        let c = 3.0 * (d1 - d0);
+
+        let square_length = segment.square_length();
+        // Allow roots to stray slightly outside either parameter range through rounding, so
+        // that crossings at endpoints aren't lost.
+        let (min_t, max_t) = (-EPSILON * EPSILON, 1.0 + EPSILON * EPSILON);
+        for t in solve_cubic(a, b, c, d0) {
+            if !(min_t..=max_t).contains(&t) {
+                continue;
+            }
+            let point = self.sample(util::clamp(t, 0.0, 1.0));
+            let segment_t = segment.vector().dot(point - segment.from()) / square_length;
+            if (min_t..=max_t).contains(&segment_t) {
+                points.push(point);
+            }
+        }
+        points
+    }
+
     #[inline]
     pub fn min_x(&self) -> f32 {
         f32::min(self.0.baseline.min_x(), self.0.ctrl.min_x())
@@ -424,3 +479,240 @@ impl<'s> CubicSegment<'s> {
         f32::max(self.0.baseline.max_y(), self.0.ctrl.max_y())
     }
 }
+
+// Returns the real roots of `at³ + bt² + ct + d`, in increasing order. Leading coefficients that
+// are negligible relative to the others are treated as zero, so that nearly degenerate cubics are
+// solved as quadratics or linear equations.
+fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> ArrayVec<[f32; 3]> {
+    let (a, b, c, d) = (a as f64, b as f64, c as f64, d as f64);
+    let mut roots: ArrayVec<[f64; 3]> = ArrayVec::new();
+    let scale = f64::max(f64::max(a.abs(), b.abs()), f64::max(c.abs(), d.abs()));
+    let negligible = |x: f64| x.abs() <= CUBIC_EPSILON * scale;
+
+    // If every coefficient is zero, every t is a root; report none.
+    if scale == 0.0 {
+        return ArrayVec::new();
+    }
+
+    if !negligible(a) {
+        // Cardano's method on the depressed cubic x³ + px + q, where t = x - b / 3a.
+        let (b, c, d) = (b / a, c / a, d / a);
+        let offset = -b / 3.0;
+        let p = c - b * b / 3.0;
+        let q = (2.0 * b * b * b - 9.0 * b * c) / 27.0 + d;
+        let discriminant = q * q / 4.0 + p * p * p / 27.0;
+        if discriminant > 0.0 {
+            let root = discriminant.sqrt();
+            roots.push((-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() + offset);
+        } else if p == 0.0 {
+            roots.push(offset);
+        } else {
+            let radius = (-p / 3.0).sqrt();
+            let cos_angle = (-q / (2.0 * radius * radius * radius)).clamp(-1.0, 1.0);
+            let angle = cos_angle.acos() / 3.0;
+            for k in 0..3 {
+                let angle = angle - 2.0 * std::f64::consts::PI * k as f64 / 3.0;
+                roots.push(2.0 * radius * angle.cos() + offset);
+            }
+        }
+
+        // Polish the roots with a step of Newton's method on the original polynomial.
+        for root in &mut roots {
+            let value = ((*root + b) * *root + c) * *root + d;
+            let slope = (3.0 * *root + 2.0 * b) * *root + c;
+            if slope != 0.0 {
+                *root -= value / slope;
+            }
+        }
+    } else if !negligible(b) {
+        let discriminant = c * c - 4.0 * b * d;
+        if discriminant >= 0.0 {
+            // Avoid cancellation by computing the larger-magnitude root first.
+            let q = -0.5 * (c + c.signum() * discriminant.sqrt());
+            if q != 0.0 {
+                roots.push(q / b);
+                roots.push(d / q);
+            } else {
+                roots.push(0.0);
+            }
+        }
+    } else if !negligible(c) {
+        roots.push(-d / c);
+    }
+
+    // A tangent line makes a double root, which rounding either splits into two nearby roots or
+    // removes altogether. Where the polynomial nearly vanishes at a critical point, replace any
+    // roots within the distance that rounding can move them with the critical point itself.
+    let tolerance = TANGENT_EPSILON * scale;
+    let (a, b) = if negligible(a) {
+        (0.0, if negligible(b) { 0.0 } else { b })
+    } else {
+        (a, b)
+    };
+    let mut critical_points: ArrayVec<[f64; 2]> = ArrayVec::new();
+    if a != 0.0 {
+        let discriminant = b * b - 3.0 * a * c;
+        if discriminant >= 0.0 {
+            critical_points.push((-b - discriminant.sqrt()) / (3.0 * a));
+            critical_points.push((-b + discriminant.sqrt()) / (3.0 * a));
+        }
+    } else if b != 0.0 {
+        critical_points.push(-c / (2.0 * b));
+    }
+    for critical_point in critical_points {
+        let value = ((a * critical_point + b) * critical_point + c) * critical_point + d;
+        if value.abs() > tolerance {
+            continue;
+        }
+        let curvature = (6.0 * a * critical_point + 2.0 * b).abs();
+        let radius = if curvature > tolerance {
+            (2.0 * tolerance / curvature).sqrt()
+        } else {
+            (tolerance / a.abs()).cbrt()
+        };
+        roots.retain(|root| (*root - critical_point).abs() > radius);
+        if !roots.is_full() {
+            roots.push(critical_point);
+        }
+    }
+
+    roots.sort_by(|a, b| a.total_cmp(b));
+    let mut result = ArrayVec::new();
+    for root in roots {
+        let root = root as f32;
+        let is_distinct = match result.last() {
+            Some(&last) => root - last > EPSILON * EPSILON,
+            None => true,
+        };
+        if is_distinct {
+            result.push(root);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::segment::{self, Segment};
+    use pathfinder_geometry::line_segment::LineSegment2F;
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+
+    fn assert_points_near(points: &[Vector2F], expected: &[Vector2F]) {
+        assert_eq!(points.len(), expected.len(), "{:?}", points);
+        for (&point, &expected) in points.iter().zip(expected.iter()) {
+            assert!((point - expected).length() < 0.0001, "{:?} {:?}", point, expected);
+        }
+    }
+
+    #[test]
+    fn test_solve_cubic() {
+        // (t - 0.2)(t - 0.5)(t - 0.9).
+        let roots = segment::solve_cubic(1.0, -1.6, 0.73, -0.09);
+        assert_eq!(roots.len(), 3);
+        for (&root, &expected) in roots.iter().zip([0.2, 0.5, 0.9].iter()) {
+            assert!(f32::abs(root - expected) < 0.0001);
+        }
+
+        // A negligible leading coefficient falls back to the quadratic (t - 0.3)(t - 0.7), and
+        // then to a linear equation.
+        let roots = segment::solve_cubic(1.0e-12, 1.0, -1.0, 0.21);
+        assert!(roots.len() == 2 && f32::abs(roots[0] - 0.3) < 0.0001 &&
+                f32::abs(roots[1] - 0.7) < 0.0001);
+        assert_eq!(&segment::solve_cubic(0.0, 0.0, 2.0, -2.0)[..], &[1.0]);
+        assert!(segment::solve_cubic(0.0, 1.0, 0.0, 1.0).is_empty());
+        assert!(segment::solve_cubic(0.0, 0.0, 0.0, 0.0).is_empty());
+
+        // The double root of t(t - 0.5)² is reported once.
+        assert_eq!(&segment::solve_cubic(1.0, -1.0, 0.25, 0.0)[..], &[0.0, 0.5]);
+
+        // NaN coefficients don't panic.
+        segment::solve_cubic(f32::NAN, 1.0, 0.0, 1.0);
+    }
+
+    #[test]
+    fn test_intersect_line() {
+        // An S-curve crossing the x axis three times.
+        let s_curve = Segment::cubic(LineSegment2F::new(vec2f(0.0, -1.0), vec2f(3.0, 1.0)),
+                                     LineSegment2F::new(vec2f(1.0, 4.0), vec2f(2.0, -4.0)));
+        let s_curve = s_curve.as_cubic_segment();
+        let axis = LineSegment2F::new(vec2f(-1.0, 0.0), vec2f(4.0, 0.0));
+        let points = s_curve.intersect_line(axis);
+        assert_points_near(&points, &[vec2f(0.2519246, 0.0), vec2f(1.5, 0.0),
+                                      vec2f(2.7480755, 0.0)]);
+
+        // Crossings beyond the ends of the line segment are dropped, as are lines that miss.
+        let short = LineSegment2F::new(vec2f(-1.0, 0.0), vec2f(1.0, 0.0));
+        assert_points_near(&s_curve.intersect_line(short), &[vec2f(0.2519246, 0.0)]);
+        let beyond = LineSegment2F::new(vec2f(3.5, 0.0), vec2f(5.0, 0.0));
+        assert!(s_curve.intersect_line(beyond).is_empty());
+        let above = LineSegment2F::new(vec2f(-1.0, 5.0), vec2f(4.0, 5.0));
+        assert!(s_curve.intersect_line(above).is_empty());
+
+        // y = 9t(1 - t)(1 - 2t) with x = 3t, which crosses the x axis at both endpoints and has
+        // a maximum of √3 / 2 at t = (3 - √3) / 6.
+        let cubic = Segment::cubic(LineSegment2F::new(vec2f(0.0, 0.0), vec2f(3.0, 0.0)),
+                                   LineSegment2F::new(vec2f(1.0, 3.0), vec2f(2.0, -3.0)));
+        let cubic = cubic.as_cubic_segment();
+        assert_points_near(&cubic.intersect_line(axis),
+                           &[vec2f(0.0, 0.0), vec2f(1.5, 0.0), vec2f(3.0, 0.0)]);
+        let max_y = f32::sqrt(0.75);
+        let tangent = LineSegment2F::new(vec2f(-1.0, max_y), vec2f(4.0, max_y));
+        assert_points_near(&cubic.intersect_line(tangent),
+                           &[vec2f(0.5 * (3.0 - f32::sqrt(3.0)), max_y)]);
+        let below = LineSegment2F::new(vec2f(-1.0, max_y - 0.01), vec2f(4.0, max_y - 0.01));
+        assert_eq!(cubic.intersect_line(below).len(), 2);
+    }
+
+    #[test]
+    fn test_intersect_line_degenerate_cubics() {
+        // A degree-elevated quadratic, y = 4t(1 - t) with x = 2t, leaves a quadratic in t.
+        let quadratic = Segment::quadratic(LineSegment2F::new(vec2f(0.0, 0.0), vec2f(2.0, 0.0)),
+                                           vec2f(1.0, 2.0)).to_cubic();
+        let quadratic = quadratic.as_cubic_segment();
+        let line = LineSegment2F::new(vec2f(3.0, 0.5), vec2f(-1.0, 0.5));
+        let offset = f32::sqrt(0.5);
+        assert_points_near(&quadratic.intersect_line(line),
+                           &[vec2f(1.0 - offset, 0.5), vec2f(1.0 + offset, 0.5)]);
+        let tangent = LineSegment2F::new(vec2f(-1.0, 1.0), vec2f(3.0, 1.0));
+        assert_points_near(&quadratic.intersect_line(tangent), &[vec2f(1.0, 1.0)]);
+        let above = LineSegment2F::new(vec2f(-1.0, 1.01), vec2f(3.0, 1.01));
+        assert!(quadratic.intersect_line(above).is_empty());
+
+        // A straight cubic leaves a linear equation.
+        let straight = Segment::cubic(LineSegment2F::new(vec2f(0.0, 0.0), vec2f(3.0, 0.0)),
+                                      LineSegment2F::new(vec2f(1.0, 0.0), vec2f(2.0, 0.0)));
+        let vertical = LineSegment2F::new(vec2f(1.5, -1.0), vec2f(1.5, 1.0));
+        assert_points_near(&straight.as_cubic_segment().intersect_line(vertical),
+                           &[vec2f(1.5, 0.0)]);
+    }
+
+    #[test]
+    fn test_flattening_segment_count() {
+        let line = Segment::cubic(LineSegment2F::new(vec2f(0.0, 0.0), vec2f(3.0, 0.0)),
+                                  LineSegment2F::new(vec2f(1.0, 0.0), vec2f(2.0, 0.0)));
+        assert_eq!(line.as_cubic_segment().flattening_segment_count(0.01), 1);
+
+        // Both second differences have length 9, so the count is ⌈√(6 · 9 / (8 · tolerance))⌉.
+        let cubic = Segment::cubic(LineSegment2F::new(vec2f(0.0, 0.0), vec2f(3.0, 0.0)),
+                                   LineSegment2F::new(vec2f(1.0, 3.0), vec2f(2.0, -3.0)));
+        let cubic = cubic.as_cubic_segment();
+        assert_eq!(cubic.flattening_segment_count(1.0), 3);
+        assert_eq!(cubic.flattening_segment_count(0.25), 6);
+        assert_eq!(cubic.flattening_segment_count(0.01), 26);
+
+        let mut last_count = 0;
+        for &tolerance in &[4.0, 1.0, 0.5, 0.25, 0.1, 0.05, 0.01] {
+            let count = cubic.flattening_segment_count(tolerance);
+            assert!(count >= last_count);
+            last_count = count;
+
+            // Each chord stays within the tolerance of the curve at its midpoint.
+            for index in 0..count {
+                let (t0, t1) = (index as f32 / count as f32, (index + 1) as f32 / count as f32);
+                let chord_midpoint = (cubic.sample(t0) + cubic.sample(t1)) * 0.5;
+                let deviation = (cubic.sample(0.5 * (t0 + t1)) - chord_midpoint).length();
+                assert!(deviation <= tolerance);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// pathfinder/content/src/shading.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Colors sampled relative to line segments, for shading strokes in software.
+
+use crate::gradient::Gradient;
+use pathfinder_color::ColorF;
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_simd::default::F32x4;
+
+pub trait SegmentShading {
+    /// Returns the premultiplied `color` scaled by the coverage of `pixel_center` by a stroke of
+    /// this segment with the given half width, softened over one pixel as in
+    /// `LineSegment2F::soft_coverage()`.
+    ///
+    /// All four channels are scaled together, so the sample blends into a premultiplied
+    /// framebuffer without dark fringes at the stroke's edges.
+    fn premultiplied_edge_sample(self, pixel_center: Vector2F, half_width: f32, color: ColorF)
+                                 -> ColorF;
+
+    /// Returns the color of a radial gradient centered on `focus` at `point`, which is typically
+    /// a point along this segment.
+    ///
+    /// The distance from the focus is normalized by `radius` and looked up in the stops of
+    /// `gradient` with `Gradient::sample()`, which clamps it to [0, 1], so points beyond the
+    /// radius take the color of the last stop. The geometry of `gradient` is ignored.
+    fn sample_radial_gradient(self,
+                              point: Vector2F,
+                              focus: Vector2F,
+                              radius: f32,
+                              gradient: &Gradient)
+                              -> ColorF;
+}
+
+impl SegmentShading for LineSegment2F {
+    #[inline]
+    fn premultiplied_edge_sample(self, pixel_center: Vector2F, half_width: f32, color: ColorF)
+                                 -> ColorF {
+        let coverage = self.soft_coverage(pixel_center, half_width, 1.0);
+        ColorF(color.0 * F32x4::splat(coverage))
+    }
+
+    #[inline]
+    fn sample_radial_gradient(self,
+                              point: Vector2F,
+                              focus: Vector2F,
+                              radius: f32,
+                              gradient: &Gradient)
+                              -> ColorF {
+        gradient.sample((point - focus).length() / radius).to_f32()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gradient::Gradient;
+    use crate::shading::SegmentShading;
+    use pathfinder_color::{ColorF, ColorU};
+    use pathfinder_geometry::line_segment::LineSegment2F;
+    use pathfinder_geometry::vector::vec2f;
+    use pathfinder_simd::default::{F32x2, F32x4};
+
+    #[test]
+    fn test_premultiplied_edge_sample() {
+        let segment = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+        let color = ColorF::new(0.4, 0.2, 0.1, 0.5);
+        let sample = segment.premultiplied_edge_sample(vec2f(5.0, 1.5), 1.0, color);
+        assert_eq!(sample.0, color.0 * F32x4::splat(0.5));
+        assert_eq!(segment.premultiplied_edge_sample(vec2f(5.0, 0.5), 1.0, color), color);
+        assert_eq!(segment.premultiplied_edge_sample(vec2f(5.0, 3.0), 1.0, color),
+                   ColorF::transparent_black());
+    }
+
+    #[test]
+    fn test_sample_radial_gradient() {
+        let segment = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+        let focus = vec2f(0.0, 0.0);
+        let mut gradient = Gradient::radial(focus, F32x2::new(0.0, 8.0));
+        let (red, green, blue) = (ColorU::new(255, 0, 0, 255),
+                                  ColorU::new(0, 255, 0, 255),
+                                  ColorU::new(0, 0, 255, 255));
+        gradient.add_color_stop(red, 0.0);
+        gradient.add_color_stop(green, 0.5);
+        gradient.add_color_stop(blue, 1.0);
+        let sample = |x: f32| {
+            segment.sample_radial_gradient(vec2f(x, 0.0), focus, 8.0, &gradient).to_u8()
+        };
+
+        assert_eq!(sample(0.0), red);
+        assert_eq!(sample(4.0), green);
+        assert_eq!(sample(8.0), blue);
+        assert_eq!(sample(10.0), blue);
+        assert_eq!(sample(2.0), gradient.sample(0.25));
+
+        // The distance is radial, not along the segment.
+        let off_axis = segment.sample_radial_gradient(vec2f(0.0, 4.0), focus, 8.0, &gradient);
+        assert_eq!(off_axis.to_u8(), green);
+        let empty = Gradient::radial(focus, F32x2::new(0.0, 8.0));
+        assert_eq!(segment.sample_radial_gradient(focus, focus, 8.0, &empty),
+                   ColorF::transparent_black());
+    }
+}
@@ -10,12 +10,33 @@
 
 //! Line segment types, optimized with SIMD.
 
+use crate::rect::RectF;
 use crate::transform2d::Matrix2x2F;
 use crate::util;
-use crate::vector::{Vector2F, vec2f};
+use crate::vector::{Vector2F, Vector2I, Vector3F, vec2f};
+use arrayvec::ArrayVec;
 use pathfinder_simd::default::F32x4;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f32::consts::PI;
 use std::ops::{Add, Mul, MulAssign, Sub};
 
+const REPARAMETERIZATION_ITERATIONS: u32 = 4;
+
+// The number of intervals that `LineSegment2F::tent_filter_coverage()` integrates over. Must be
+// a multiple of 4.
+const TENT_FILTER_INTERVALS: u32 = 16;
+
+// The largest finite half-precision float.
+const F16_MAX: f32 = 65504.0;
+
+// The width and height of Pathfinder's area lookup table, `resources/textures/area-lut.png`.
+const AREA_LUT_SIZE: u32 = 256;
+
+// The most segments that `arc_through_three_points()` flattens an arc into. Arcs that would need
+// more to meet the tolerance are flattened less finely.
+const MAX_ARC_SEGMENTS: usize = 4096;
+
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct LineSegment2F(pub F32x4);
 
@@ -144,11 +165,165 @@ impl LineSegment2F {
         util::lerp(self.from_y(), self.to_y(), self.solve_t_for_x(x))
     }
 
+    // Returns the point where this segment crosses the horizontal line at `y`, or `None` if it
+    // doesn't reach the line or is parallel to it.
+    #[inline]
+    pub fn intersect_horizontal_line(self, y: f32) -> Option<Vector2F> {
+        if self.from_y() == self.to_y() || y < self.min_y() || y > self.max_y() {
+            None
+        } else {
+            Some(vec2f(self.solve_x_for_y(y), y))
+        }
+    }
+
+    // Returns the point where this segment crosses the vertical line at `x`, or `None` if it
+    // doesn't reach the line or is parallel to it.
+    #[inline]
+    pub fn intersect_vertical_line(self, x: f32) -> Option<Vector2F> {
+        if self.from_x() == self.to_x() || x < self.min_x() || x > self.max_x() {
+            None
+        } else {
+            Some(vec2f(x, self.solve_y_for_x(x)))
+        }
+    }
+
+    // Returns the points where this segment crosses the boundary of the capsule formed by
+    // inflating `axis` by `radius`, ordered from `from` to `to`. The capsule is convex, so there
+    // are at most two crossings; a segment tangent to the boundary yields a single point.
+    pub fn capsule_boundary_intersections(self, axis: LineSegment2F, radius: f32)
+                                          -> ArrayVec<[Vector2F; 2]> {
+        let mut ts: Vec<f32> = vec![];
+        let (origin, vector) = (self.from(), self.vector());
+        let axis_vector = axis.vector();
+        let axis_square_length = axis_vector.square_length();
+        let axis_t = |point: Vector2F| {
+            if axis_square_length == 0.0 {
+                0.0
+            } else {
+                axis_vector.projection_coefficient(point - axis.from())
+            }
+        };
+
+        // The flat sides, wherever the crossing projects onto the axis.
+        if axis_square_length > 0.0 {
+            let normal = axis_vector.yx().normalize() * vec2f(-1.0, 1.0);
+            let (start, rate) = (normal.dot(origin - axis.from()), normal.dot(vector));
+            if rate != 0.0 {
+                for &side in &[-radius, radius] {
+                    let t = (side - start) / rate;
+                    let axis_t = axis_t(origin + vector * t);
+                    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&axis_t) {
+                        ts.push(t);
+                    }
+                }
+            }
+        }
+
+        // The caps, wherever the crossing projects beyond the ends of the axis.
+        let a = vector.square_length();
+        for &(center, beyond_start) in &[(axis.from(), true), (axis.to(), false)] {
+            let offset = origin - center;
+            let (b, c) = (2.0 * offset.dot(vector), offset.square_length() - radius * radius);
+            let discriminant = b * b - 4.0 * a * c;
+            if a == 0.0 || discriminant < 0.0 {
+                continue;
+            }
+            let root = f32::sqrt(discriminant);
+            for &t in &[(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)] {
+                let axis_t = axis_t(origin + vector * t);
+                let on_cap = if beyond_start { axis_t < 0.0 } else { axis_t > 1.0 };
+                if (0.0..=1.0).contains(&t) && (on_cap || axis_square_length == 0.0) {
+                    ts.push(t);
+                }
+            }
+        }
+
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup_by(|a, b| f32::abs(*a - *b) < util::EPSILON);
+        let mut points = ArrayVec::new();
+        if let (Some(&first), Some(&last)) = (ts.first(), ts.last()) {
+            points.push(self.sample(first));
+            if last > first {
+                points.push(self.sample(last));
+            }
+        }
+        points
+    }
+
     #[inline]
     pub fn reversed(self) -> LineSegment2F {
         LineSegment2F(self.0.zwxy())
     }
 
+    // Reverses if necessary so that `from_x() <= to_x()`, and, for vertical segments, so that
+    // `from_y() <= to_y()`. This gives every segment a unique orientation for sweep-line
+    // processing.
+    #[inline]
+    pub fn to_x_monotone(self) -> LineSegment2F {
+        if self.from_x() < self.to_x() ||
+                (self.from_x() == self.to_x() && self.from_y() <= self.to_y()) {
+            self
+        } else {
+            self.reversed()
+        }
+    }
+
+    // Returns the events at this segment's left and right endpoints, in that order, for a
+    // left-to-right sweep over the segments of the polygon `polygon_id`. The left endpoint is the
+    // `from` point of `to_x_monotone()`.
+    #[inline]
+    pub fn to_sweep_events(self, polygon_id: u8) -> (SweepEvent, SweepEvent) {
+        let segment = self.to_x_monotone();
+        let (left, right) = (segment.from(), segment.to());
+        (SweepEvent { point: left, other_point: right, polygon_id, is_left: true },
+         SweepEvent { point: right, other_point: left, polygon_id, is_left: false })
+    }
+
+    // Orders this segment relative to `other` in a sweep-line status structure at the sweep
+    // position `x`: by their y coordinates at `x`, then by slope, and finally by endpoints, so
+    // that distinct segments never compare equal. Vertical segments are placed at their upper
+    // end and sort after every other slope. Everything is computed in double precision, and no
+    // tolerance is applied, since tolerances would make the order intransitive.
+    pub fn compare_at_x(self, other: LineSegment2F, x: f32) -> Ordering {
+        fn y_at_x(segment: LineSegment2F, x: f64) -> f64 {
+            let (from_x, from_y) = (segment.from_x() as f64, segment.from_y() as f64);
+            let (dx, dy) = (segment.to_x() as f64 - from_x, segment.to_y() as f64 - from_y);
+            if dx == 0.0 {
+                f64::min(from_y, segment.to_y() as f64)
+            } else {
+                from_y + (x - from_x) * (dy / dx)
+            }
+        }
+
+        let (this, other) = (self.to_x_monotone(), other.to_x_monotone());
+        let x = x as f64;
+        match y_at_x(this, x).partial_cmp(&y_at_x(other, x)) {
+            Some(Ordering::Equal) | None => {}
+            Some(ordering) => return ordering,
+        }
+
+        // With both segments running left to right, compare `dy_a / dx_a` to `dy_b / dx_b`
+        // without dividing. Vertical segments come out as the steepest.
+        let delta = |segment: LineSegment2F| {
+            (segment.to_x() as f64 - segment.from_x() as f64,
+             segment.to_y() as f64 - segment.from_y() as f64)
+        };
+        let ((this_dx, this_dy), (other_dx, other_dy)) = (delta(this), delta(other));
+        let slope_ordering = if this_dx == 0.0 || other_dx == 0.0 {
+            (this_dx == 0.0).cmp(&(other_dx == 0.0))
+        } else {
+            (this_dy * other_dx).partial_cmp(&(other_dy * this_dx)).unwrap_or(Ordering::Equal)
+        };
+        if slope_ordering != Ordering::Equal {
+            return slope_ordering;
+        }
+
+        let key = |segment: LineSegment2F| {
+            [segment.from_x(), segment.from_y(), segment.to_x(), segment.to_y()]
+        };
+        key(this).partial_cmp(&key(other)).unwrap_or(Ordering::Equal)
+    }
+
     #[inline]
     pub fn upper_point(self) -> Vector2F {
         if self.from_y() < self.to_y() {
@@ -178,6 +353,18 @@ impl LineSegment2F {
         f32::max(self.from_y(), self.to_y())
     }
 
+    #[inline]
+    pub fn bounds(self) -> RectF {
+        RectF::from_points(self.from().min(self.to()), self.from().max(self.to()))
+    }
+
+    // Returns this segment's bounds, for a bounding volume hierarchy leaf, along with its
+    // midpoint as the centroid to partition leaves by.
+    #[inline]
+    pub fn bvh_primitive(self) -> (RectF, Vector2F) {
+        (self.bounds(), self.midpoint())
+    }
+
     #[inline]
     pub fn y_winding(self) -> i32 {
         if self.from_y() < self.to_y() {
@@ -215,6 +402,23 @@ impl LineSegment2F {
         self.to() - self.from()
     }
 
+    // Returns the coefficients `(a, b, c)` of the implicit equation `ax + by + c = 0` of the line
+    // through this segment. `(a, b)` is normal to the segment, with the length of the segment.
+    #[inline]
+    pub fn line_coords(self) -> Vector3F {
+        let (from, to) = (self.from(), self.to());
+        Vector3F::new(from.x(), from.y(), 1.0).cross(Vector3F::new(to.x(), to.y(), 1.0))
+    }
+
+    // Returns the intersection of the lines through this segment and `other` in homogeneous
+    // coordinates, as `[x, y, w, 0]`: the point is `(x / w, y / w)`, and `w` is zero if the lines
+    // are parallel. Deferring the division lets callers check for parallel lines and divide many
+    // intersections at once.
+    #[inline]
+    pub fn intersection_homogeneous(self, other: LineSegment2F) -> F32x4 {
+        self.line_coords().cross(other.line_coords()).0
+    }
+
     // http://www.cs.swan.ac.uk/~cssimon/line_intersection.html
     pub fn intersection_t(self, other: LineSegment2F) -> Option<f32> {
         let p0p1 = self.vector();
@@ -246,63 +450,3248 @@ impl LineSegment2F {
         }
     }
 
+    // Returns the unit vector pointing from `from` to `to`, or zero if the segment is
+    // zero-length.
+    #[inline]
+    pub fn direction(self) -> Vector2F {
+        if self.is_zero_length() {
+            Vector2F::zero()
+        } else {
+            self.vector().normalize()
+        }
+    }
+
+    // Reflects this segment through `center`, which is the same as rotating it by 180° about
+    // that point.
+    #[inline]
+    pub fn reflect_through_point(self, center: Vector2F) -> LineSegment2F {
+        LineSegment2F(center.0.to_f32x4().xyxy() * F32x4::splat(2.0) - self.0)
+    }
+
     #[inline]
     pub fn is_zero_length(self) -> bool {
         self.vector().is_zero()
     }
-}
 
-impl Add<Vector2F> for LineSegment2F {
-    type Output = LineSegment2F;
+    // Converts each coordinate, in the order `from_x`, `from_y`, `to_x`, `to_y`, to the bits of
+    // an IEEE 754 half-precision float, rounding to nearest even. Half precision keeps 11
+    // significant bits, so coordinates in `[0, 16]` are off by at most 2⁻⁷. Magnitudes beyond
+    // the largest finite half, 65504, are clamped to it. Magnitudes below 2⁻¹⁴ become subnormal
+    // halves, losing precision gradually, and those below 2⁻²⁵ become zero.
     #[inline]
-    fn add(self, point: Vector2F) -> LineSegment2F {
-        LineSegment2F(self.0 + point.0.to_f32x4().xyxy())
+    pub fn to_f16(self) -> [u16; 4] {
+        [f32_to_f16(self.from_x()), f32_to_f16(self.from_y()), f32_to_f16(self.to_x()),
+         f32_to_f16(self.to_y())]
     }
-}
 
-impl Sub<Vector2F> for LineSegment2F {
-    type Output = LineSegment2F;
+    // Reconstructs a segment from half-precision coordinates produced by `to_f16()`.
     #[inline]
-    fn sub(self, point: Vector2F) -> LineSegment2F {
-        LineSegment2F(self.0 - point.0.to_f32x4().xyxy())
+    pub fn from_f16(bits: [u16; 4]) -> LineSegment2F {
+        LineSegment2F(F32x4::new(f16_to_f32(bits[0]), f16_to_f32(bits[1]), f16_to_f32(bits[2]),
+                                 f16_to_f32(bits[3])))
     }
-}
 
-impl Mul<Vector2F> for LineSegment2F {
-    type Output = LineSegment2F;
+    // Accumulates this segment's coverage of the pixel row `[scanline_y, scanline_y + 1)` into
+    // `coverage`, whose first cell is the pixel starting at `x_origin`.
+    //
+    // The contributions are signed by `y_winding()` and stored as deltas, so a running sum across
+    // the row yields each pixel's coverage. The pixels the segment crosses receive the exact
+    // partial area to the right of the segment. The part of the segment left of `x_origin`
+    // contributes to the first cell as if it were vertical there. Contributions past the end of
+    // the buffer are dropped, so `coverage` should be one cell wider than the row.
+    pub fn accumulate_into_scanline(self, scanline_y: f32, coverage: &mut [f32], x_origin: f32) {
+        let y_winding = self.y_winding();
+        let segment = self.orient(y_winding);
+        let y0 = f32::max(segment.from_y(), scanline_y);
+        let y1 = f32::min(segment.to_y(), scanline_y + 1.0);
+        if y1 <= y0 {
+            return;
+        }
+
+        let x0 = segment.solve_x_for_y(y0) - x_origin;
+        let x1 = segment.solve_x_for_y(y1) - x_origin;
+        accumulate_span(x0, x1, (y1 - y0) * y_winding as f32, |index, value| {
+            if let Some(cell) = coverage.get_mut(index) {
+                *cell += value;
+            }
+        });
+    }
+
+    // Accumulates this segment's coverage of the pixel row `[scanline_y, scanline_y + 1)` into
+    // `coverage`, whose first cell is the pixel starting at `x_origin`, computed as Pathfinder's
+    // fill shader computes it (`computeCoverage()` in `shaders/fill.inc.glsl`) and converted to
+    // fixed point with `fractional_bits` bits of fraction.
+    //
+    // Unlike `accumulate_into_scanline()`, each cell receives a coverage rather than a delta: for
+    // every pixel whose column the segment spans, the shader looks up the area of the pixel below
+    // the segment in Pathfinder's area lookup table, signed by the segment's direction in x, so a
+    // pixel's coverage is the sum of the contributions of all of a path's segments, including
+    // those wholly above the row. The table is evaluated as `utils/area-lut` generates the
+    // `area-lut` texture, with 8-bit texels, and sampled bilinearly with clamping to its edges, as
+    // the GPU samples it. As on the GPU, rows above the 4-pixel strip containing the top of the
+    // segment receive nothing, and vertical segments, which Pathfinder never emits as fills,
+    // contribute nothing.
+    //
+    // Each pixel's contribution is rounded to the nearest step, with ties away from zero, before it
+    // is added, so the result doesn't depend on the order in which segments are added. It matches
+    // the GPU's up to the precision of the GPU's interpolation and texture filtering, which GL
+    // leaves to the implementation.
+    pub fn accumulate_coverage_fixed(self,
+                                     coverage: &mut [i32],
+                                     x_origin: f32,
+                                     scanline_y: f32,
+                                     fractional_bits: u32) {
+        debug_assert!(fractional_bits < 31);
+        if self.from_x() == self.to_x() ||
+                (scanline_y * 0.25).floor() < (self.min_y() * 0.25).floor() {
+            return;
+        }
+
+        let scale = (1 << fractional_bits) as f32;
+        let first_column = f32::max((self.min_x() - x_origin).floor(), 0.0) as usize;
+        let last_column = f32::max((self.max_x() - x_origin).ceil(), 0.0) as usize;
+        let cells = coverage.iter_mut().enumerate().take(last_column).skip(first_column);
+        for (column, cell) in cells {
+            let pixel_center = vec2f(x_origin + column as f32 + 0.5, scanline_y + 0.5);
+            let value = fill_shader_coverage(self.from() - pixel_center, self.to() - pixel_center);
+            *cell += (value * scale).round() as i32;
+        }
+    }
+
+    // Like `accumulate_into_scanline()`, but for a row of square cells `tile_size` wide,
+    // starting at x = 0. The segment must already be clipped to the row. Each cell's
+    // contribution is added with Kahan summation, carrying the running error in the
+    // `compensation` array, which mirrors `row_coverage`. Callers zero-initialize both.
+    pub fn accumulate_coverage_compensated(self,
+                                           row_coverage: &mut [f32],
+                                           compensation: &mut [f32],
+                                           tile_size: f32) {
+        debug_assert_eq!(row_coverage.len(), compensation.len());
+        if self.from_y() == self.to_y() {
+            return;
+        }
+
+        let scale = 1.0 / tile_size;
+        let d = (self.to_y() - self.from_y()) * scale;
+        accumulate_span(self.from_x() * scale, self.to_x() * scale, d, |index, value| {
+            if let (Some(sum), Some(error)) = (row_coverage.get_mut(index),
+                                               compensation.get_mut(index)) {
+                let value = value - *error;
+                let new_sum = *sum + value;
+                *error = (new_sum - *sum) - value;
+                *sum = new_sum;
+            }
+        });
+    }
+
+    // Accumulates this segment's coverage of the row `tile_y_range` of unit-wide cells starting
+    // at x = 0, split like Pathfinder's fills and backdrops. Where the segment crosses the row's
+    // top edge, its `y_winding()` is added to the backdrop of the next cell, and the fraction of
+    // the crossed cell to the right of the crossing to its area. Within the row, the signed area
+    // below the segment is added to the area of each cell it spans.
+    //
+    // After every segment of a closed path has been added, each cell's coverage is the prefix sum
+    // of `row_backdrops` up to and including it, plus its entry in `row_areas`. The top edge is
+    // treated as half-open, like `subpixel_coverage_events()`.
+    pub fn add_area_prefix(self,
+                           row_areas: &mut [f32],
+                           row_backdrops: &mut [i32],
+                           tile_y_range: (f32, f32)) {
+        let (y_top, y_bottom) = tile_y_range;
+        let y_winding = self.y_winding();
+        if self.min_y() <= y_top && y_top < self.max_y() {
+            let x = self.solve_x_for_y(y_top);
+            if x < 0.0 {
+                if let Some(backdrop) = row_backdrops.get_mut(0) {
+                    *backdrop += y_winding;
+                }
+            } else {
+                let column = x as usize;
+                if let Some(area) = row_areas.get_mut(column) {
+                    *area += (column as f32 + 1.0 - x) * y_winding as f32;
+                }
+                if let Some(backdrop) = row_backdrops.get_mut(column + 1) {
+                    *backdrop += y_winding;
+                }
+            }
+        }
+
+        let segment = match self.clip_to_y_strip(y_top, y_bottom) {
+            Some(segment) if segment.max_y() > y_top => segment,
+            _ => return,
+        };
+        let first_column = f32::max(segment.min_x(), 0.0) as usize;
+        let last_column = f32::max(segment.max_x(), 0.0) as usize + 1;
+        let cells = row_areas.iter_mut().enumerate().take(last_column).skip(first_column);
+        for (column, area) in cells {
+            let cell = RectF::new(vec2f(column as f32, y_top), vec2f(1.0, y_bottom - y_top));
+            *area -= segment.exact_pixel_coverage(cell);
+        }
+    }
+
+    // Splits this segment at every tile boundary of a grid of square tiles `tile_size` wide,
+    // returning the coordinates of each tile the segment passes through along with the piece of
+    // the segment inside it, ordered from `from` to `to`. Zero-length pieces, such as those where
+    // the segment passes exactly through a tile corner, are omitted. `tile_size` must be positive;
+    // otherwise no pieces are returned.
+    pub fn split_into_tiles(self, tile_size: f32) -> Vec<(i32, i32, LineSegment2F)> {
+        debug_assert!(tile_size > 0.0);
+        if tile_size <= 0.0 {
+            return vec![];
+        }
+
+        let mut ts = vec![0.0, 1.0];
+        let mut add_crossings = |from: f32, to: f32| {
+            if from == to {
+                return;
+            }
+            let (min, max) = (f32::min(from, to), f32::max(from, to));
+            for index in (min / tile_size).ceil() as i32..=(max / tile_size).floor() as i32 {
+                let t = (index as f32 * tile_size - from) / (to - from);
+                if t > 0.0 && t < 1.0 {
+                    ts.push(t);
+                }
+            }
+        };
+        add_crossings(self.from_x(), self.to_x());
+        add_crossings(self.from_y(), self.to_y());
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut pieces = vec![];
+        for pair in ts.windows(2) {
+            let piece = self.slice(pair[0], pair[1]);
+            if piece.is_zero_length() {
+                continue;
+            }
+            let tile = (piece.midpoint() * (1.0 / tile_size)).floor().to_i32();
+            pieces.push((tile.x(), tile.y(), piece));
+        }
+        pieces
+    }
+
+    // Like `split_into_tiles()`, but each split point is computed once and shared bit-for-bit by
+    // the pieces on either side of it, with its coordinate along the crossed boundary set to the
+    // boundary exactly. Coverage accumulated from the tile-local pieces therefore sums across
+    // tile edges without seams. A crossing within `util::EPSILON` of a tile corner is moved onto
+    // the corner. As with `split_into_tiles()`, `tile_size` must be positive.
+    pub fn split_into_tiles_seamless(self, tile_size: f32) -> Vec<(i32, i32, LineSegment2F)> {
+        debug_assert!(tile_size > 0.0);
+        if tile_size <= 0.0 {
+            return vec![];
+        }
+
+        let mut crossings = vec![];
+        for &(from, to, is_x) in &[(self.from_x(), self.to_x(), true),
+                                  (self.from_y(), self.to_y(), false)] {
+            if from == to {
+                continue;
+            }
+            let (min, max) = (f32::min(from, to), f32::max(from, to));
+            for index in (min / tile_size).ceil() as i32..=(max / tile_size).floor() as i32 {
+                let boundary = index as f32 * tile_size;
+                let t = (boundary - from) / (to - from);
+                if t > 0.0 && t < 1.0 {
+                    let mut point = self.sample(t);
+                    if is_x {
+                        point.set_x(boundary);
+                    } else {
+                        point.set_y(boundary);
+                    }
+                    crossings.push((t, is_x, point));
+                }
+            }
+        }
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut points = vec![self.from()];
+        let mut last_is_x = None;
+        for (_, is_x, point) in crossings {
+            let last = points.last_mut().unwrap();
+            if last_is_x == Some(!is_x) && (point - *last).length() < util::EPSILON {
+                if is_x {
+                    last.set_x(point.x());
+                } else {
+                    last.set_y(point.y());
+                }
+                last_is_x = None;
+                continue;
+            }
+            points.push(point);
+            last_is_x = Some(is_x);
+        }
+        points.push(self.to());
+
+        let mut pieces = vec![];
+        for pair in points.windows(2) {
+            let piece = LineSegment2F::new(pair[0], pair[1]);
+            if piece.is_zero_length() {
+                continue;
+            }
+            let tile = (piece.midpoint() * (1.0 / tile_size)).floor().to_i32();
+            pieces.push((tile.x(), tile.y(), piece));
+        }
+        pieces
+    }
+
+    // Returns a suggested number of coverage samples for this segment, such that the staircase
+    // error of supersampling it stays under `target_error`. The count grows linearly with length
+    // and with `sin(2θ)`, so it peaks for diagonal segments; axis-aligned and zero-length
+    // segments need only the minimum of 1 sample.
+    pub fn recommended_samples(self, target_error: f32) -> u32 {
+        debug_assert!(target_error > 0.0);
+        let length = self.length();
+        if length == 0.0 {
+            return 1;
+        }
+
+        // length * sin(2θ) / (4 * target_error), with sin(2θ) = 2|dx dy| / length².
+        let vector = self.vector();
+        let samples = f32::abs(vector.x() * vector.y()) / (2.0 * length * target_error);
+        u32::max(1, f32::ceil(samples) as u32)
+    }
+
+    // Returns the point `distance` units along this segment from `from`.
     #[inline]
-    fn mul(self, factors: Vector2F) -> LineSegment2F {
-        LineSegment2F(self.0 * factors.0.to_f32x4().xyxy())
+    pub fn point_at_distance(self, distance: f32) -> Vector2F {
+        let length = self.length();
+        if length == 0.0 {
+            self.from()
+        } else {
+            self.sample(distance / length)
+        }
     }
-}
 
-impl Mul<f32> for LineSegment2F {
-    type Output = LineSegment2F;
+    // Returns the portion of this segment between the arc distances `start` and `end` from
+    // `from`, clamped to the segment, or `None` if the window doesn't overlap it. The endpoints
+    // are the `point_at_distance()` of the clamped distances.
+    pub fn trim_to_distance_range(self, start: f32, end: f32) -> Option<LineSegment2F> {
+        let length = self.length();
+        if start > end || end < 0.0 || start > length {
+            return None;
+        }
+        Some(LineSegment2F::new(self.point_at_distance(f32::max(start, 0.0)),
+                                self.point_at_distance(f32::min(end, length))))
+    }
+
+    // Returns the signed area of the trapezoid between this segment and the vertical line at
+    // `reference_x`, integrated over the segment's y extent. The area is positive when the segment
+    // lies to the right of the line and its `y_winding()` is positive.
     #[inline]
-    fn mul(self, factor: f32) -> LineSegment2F {
-        LineSegment2F(self.0 * F32x4::splat(factor))
+    pub fn trapezoid_area(self, reference_x: f32) -> f32 {
+        let mid_x = 0.5 * (self.from_x() + self.to_x());
+        (mid_x - reference_x) * (self.to_y() - self.from_y())
     }
-}
 
-impl MulAssign<Vector2F> for LineSegment2F {
+    // Returns the portion of this segment with x in `[x_left, x_right]`, preserving its
+    // direction, or `None` if the segment lies entirely outside the strip.
+    pub fn clip_to_x_strip(self, x_left: f32, x_right: f32) -> Option<LineSegment2F> {
+        if self.max_x() < x_left || self.min_x() > x_right {
+            return None;
+        }
+        if self.from_x() == self.to_x() {
+            return Some(self);
+        }
+
+        let (t0, t1) = (self.solve_t_for_x(x_left), self.solve_t_for_x(x_right));
+        let mut segment = self.slice(f32::min(t0, t1), f32::max(t0, t1));
+        segment.set_from_x(util::clamp(segment.from_x(), x_left, x_right));
+        segment.set_to_x(util::clamp(segment.to_x(), x_left, x_right));
+        Some(segment)
+    }
+
+    // Returns the portion of this segment with y in `[y_top, y_bottom]`, preserving its
+    // direction, or `None` if the segment lies entirely outside the strip.
+    pub fn clip_to_y_strip(self, y_top: f32, y_bottom: f32) -> Option<LineSegment2F> {
+        if self.max_y() < y_top || self.min_y() > y_bottom {
+            return None;
+        }
+        if self.from_y() == self.to_y() {
+            return Some(self);
+        }
+
+        let (t0, t1) = (self.solve_t_for_y(y_top), self.solve_t_for_y(y_bottom));
+        let mut segment = self.slice(f32::min(t0, t1), f32::max(t0, t1));
+        segment.set_from_y(util::clamp(segment.from_y(), y_top, y_bottom));
+        segment.set_to_y(util::clamp(segment.to_y(), y_top, y_bottom));
+        Some(segment)
+    }
+
+    // Returns the portion of this segment inside `rect`, preserving its direction, or `None` if
+    // the segment lies entirely outside it.
     #[inline]
-    fn mul_assign(&mut self, factors: Vector2F) {
-        *self = *self * factors
+    pub fn clip_to_rect(self, rect: RectF) -> Option<LineSegment2F> {
+        self.clip_to_x_strip(rect.min_x(), rect.max_x())?
+            .clip_to_y_strip(rect.min_y(), rect.max_y())
     }
-}
 
-#[derive(Clone, Copy, Debug, Default)]
-#[repr(C)]
-pub struct LineSegmentU4 {
-    pub from: u8,
-    pub to: u8,
-}
+    // Clips this segment to `viewport` dilated by `guard_band` on every side, so that clipping
+    // happens far enough off screen that the cut ends of strokes aren't visible. Returns `None`
+    // only if the segment lies entirely outside the dilated viewport.
+    #[inline]
+    pub fn clip_to_viewport(self, viewport: RectF, guard_band: f32) -> Option<LineSegment2F> {
+        self.clip_to_rect(viewport.dilate(guard_band))
+    }
 
-#[derive(Clone, Copy, Debug, Default)]
-#[repr(C)]
-pub struct LineSegmentU8 {
-    pub from_x: u8,
-    pub from_y: u8,
-    pub to_x: u8,
-    pub to_y: u8,
+    // Returns the point on this segment closest to `point`.
+    #[inline]
+    pub fn closest_point(self, point: Vector2F) -> Vector2F {
+        let vector = self.vector();
+        if vector.is_zero() {
+            return self.from();
+        }
+        let t = util::clamp(vector.projection_coefficient(point - self.from()), 0.0, 1.0);
+        self.sample(t)
+    }
+
+    #[inline]
+    pub fn distance_to_point(self, point: Vector2F) -> f32 {
+        (point - self.closest_point(point)).length()
+    }
+
+    // Returns the gradient of the distance to this segment at `point`: the unit vector from the
+    // closest point on the segment to `point`, or zero if `point` lies on the segment.
+    #[inline]
+    pub fn sdf_gradient(self, point: Vector2F) -> Vector2F {
+        let offset = point - self.closest_point(point);
+        if offset.is_zero() {
+            offset
+        } else {
+            offset.normalize()
+        }
+    }
+
+    // Returns the coverage of `pixel_center` by a stroke of this segment with the given half
+    // width: 1 within the stroke, falling off linearly to 0 over `softness` beyond its edge.
+    #[inline]
+    pub fn soft_coverage(self, pixel_center: Vector2F, half_width: f32, softness: f32) -> f32 {
+        let distance = self.distance_to_point(pixel_center) - half_width;
+        if softness <= 0.0 {
+            return if distance <= 0.0 { 1.0 } else { 0.0 };
+        }
+        util::clamp(1.0 - distance / softness, 0.0, 1.0)
+    }
+
+    // Writes `clamp(1 - distance / spread, 0, 1)` for the center of each pixel of a grid with the
+    // given resolution laid over `bounds` into the row-major `mask`, keeping any larger value
+    // already there. Four pixels are processed at a time. `spread` must be positive.
+    pub fn rasterize_distance_mask(self,
+                                   bounds: RectF,
+                                   resolution: (u32, u32),
+                                   spread: f32,
+                                   mask: &mut [f32]) {
+        debug_assert!(spread > 0.0);
+        let (width, height) = (resolution.0 as usize, resolution.1 as usize);
+        debug_assert!(mask.len() >= width * height);
+
+        let pixel_size = bounds.size() / vec2f(width as f32, height as f32);
+        let vector = self.vector();
+        let square_length = vector.square_length();
+        let inv_square_length = if square_length == 0.0 { 0.0 } else { 1.0 / square_length };
+        let (vector_x, vector_y) = (F32x4::splat(vector.x()), F32x4::splat(vector.y()));
+        let lane_offsets = F32x4::new(0.5, 1.5, 2.5, 3.5) * F32x4::splat(pixel_size.x());
+        let (zero, one) = (F32x4::splat(0.0), F32x4::splat(1.0));
+
+        for row in 0..height {
+            let y = bounds.origin_y() + (row as f32 + 0.5) * pixel_size.y();
+            let dy = F32x4::splat(y - self.from_y());
+            for col in (0..width).step_by(4) {
+                let x = bounds.origin_x() + col as f32 * pixel_size.x();
+                let dx = F32x4::splat(x - self.from_x()) + lane_offsets;
+                let t = ((dx * vector_x + dy * vector_y) * F32x4::splat(inv_square_length))
+                    .clamp(zero, one);
+                let (ex, ey) = (dx - t * vector_x, dy - t * vector_y);
+                let distance = (ex * ex + ey * ey).sqrt();
+                let alpha = (one - distance * F32x4::splat(1.0 / spread)).clamp(zero, one);
+
+                for lane in 0..usize::min(4, width - col) {
+                    let value = &mut mask[row * width + col + lane];
+                    *value = f32::max(*value, alpha[lane]);
+                }
+            }
+        }
+    }
+
+    // Returns the winding deltas of this segment for a grid with the given resolution laid over
+    // `bounds`, as `(column, row, y_winding)` tuples: one for each pixel row whose center line the
+    // segment crosses, at the first column whose center lies to the right of the crossing.
+    // Prefix-summing the deltas across each row yields the winding number at every pixel center.
+    // Crossings to the right of the grid are dropped.
+    pub fn edge_deltas(self, bounds: RectF, resolution: (u32, u32)) -> Vec<(u32, u32, i32)> {
+        let mut deltas = vec![];
+        if self.from_y() == self.to_y() {
+            return deltas;
+        }
+
+        let pixel_size = bounds.size() / vec2f(resolution.0 as f32, resolution.1 as f32);
+        let to_row = |y: f32| {
+            let row = ((y - bounds.origin_y()) / pixel_size.y() - 0.5).ceil();
+            util::clamp(row, 0.0, resolution.1 as f32) as u32
+        };
+
+        let y_winding = self.y_winding();
+        for row in to_row(self.min_y())..to_row(self.max_y()) {
+            let y = bounds.origin_y() + (row as f32 + 0.5) * pixel_size.y();
+            let x = (self.solve_x_for_y(y) - bounds.origin_x()) / pixel_size.x();
+            let column = f32::max((x - 0.5).ceil(), 0.0) as u32;
+            if column < resolution.0 {
+                deltas.push((column, row, y_winding));
+            }
+        }
+        deltas
+    }
+
+    // Returns the crossings of this segment for a grid laid over `bounds`, sampled exactly as in
+    // `edge_deltas()` but without the winding sign. XOR-ing a flag at each `(column, row)` and
+    // prefix-XOR-ing across each row yields the even-odd parity at every pixel center.
+    pub fn parity_crossings(self, bounds: RectF, resolution: (u32, u32)) -> Vec<(u32, u32)> {
+        self.edge_deltas(bounds, resolution)
+            .into_iter()
+            .map(|(column, row, _)| (column, row))
+            .collect()
+    }
+
+    // Returns every pixel of a grid laid over `bounds` that the segment, dilated by half a pixel,
+    // could touch, as `(column, row)` tuples in row-major order. A pixel is included whenever the
+    // segment comes within half a pixel of it along both axes, which may include a few pixels
+    // that the dilated segment misses at its corners, but never omits one it touches.
+    pub fn conservative_pixels(self, bounds: RectF, resolution: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut pixels = vec![];
+        let pixel_size = bounds.size() / vec2f(resolution.0 as f32, resolution.1 as f32);
+        let scale = vec2f(1.0 / pixel_size.x(), 1.0 / pixel_size.y());
+        let segment = LineSegment2F::new((self.from() - bounds.origin()) * scale,
+                                         (self.to() - bounds.origin()) * scale);
+
+        // Pixel `[c, c + 1]` touches the dilated segment iff the segment touches
+        // `[c - 0.5, c + 1.5]`, on each axis.
+        let to_range = |min: f32, max: f32, count: u32| {
+            let first = util::clamp((min - 1.5).ceil(), 0.0, count as f32) as u32;
+            let last = util::clamp((max + 0.5).floor() + 1.0, 0.0, count as f32) as u32;
+            first..last
+        };
+        for row in to_range(segment.min_y(), segment.max_y(), resolution.1) {
+            let strip = match segment.clip_to_y_strip(row as f32 - 0.5, row as f32 + 1.5) {
+                None => continue,
+                Some(strip) => strip,
+            };
+            for column in to_range(strip.min_x(), strip.max_x(), resolution.0) {
+                pixels.push((column, row));
+            }
+        }
+        pixels
+    }
+
+    // Rounds both endpoints to the nearest multiple of `grid`.
+    #[inline]
+    pub fn snap_to_grid(self, grid: f32) -> LineSegment2F {
+        let snap = |point: Vector2F| (point * (1.0 / grid)).round() * grid;
+        LineSegment2F::new(snap(self.from()), snap(self.to()))
+    }
+
+    // Like `snap_to_grid()`, but if snapping would collapse the segment along its dominant axis,
+    // moves the `to` endpoint one grid unit along that axis so that the snapped segment keeps the
+    // direction and non-zero length of the original.
+    pub fn snap_preserving_slope(self, grid: f32) -> LineSegment2F {
+        let mut snapped = self.snap_to_grid(grid);
+        let (vector, snapped_vector) = (self.vector(), snapped.vector());
+        if vector.is_zero() {
+            return snapped;
+        }
+
+        if f32::abs(vector.x()) >= f32::abs(vector.y()) {
+            if snapped_vector.x() * vector.x() <= 0.0 {
+                snapped.set_to_x(snapped.from_x() + grid * vector.x().signum());
+            }
+        } else if snapped_vector.y() * vector.y() <= 0.0 {
+            snapped.set_to_y(snapped.from_y() + grid * vector.y().signum());
+        }
+        snapped
+    }
+
+    // Rotates `to` about `from` so that this segment's angle becomes the nearest multiple of
+    // `2π / divisions`, keeping its length. Zero-length segments are returned unchanged.
+    pub fn snap_angle(self, divisions: u32) -> LineSegment2F {
+        debug_assert!(divisions > 0);
+        let vector = self.vector();
+        if vector.is_zero() {
+            return self;
+        }
+        let step = 2.0 * PI / divisions as f32;
+        let angle = (f32::atan2(vector.y(), vector.x()) / step).round() * step;
+        let direction = vec2f(angle.cos(), angle.sin());
+        LineSegment2F::new(self.from(), self.from() + direction * self.length())
+    }
+
+    // Adds `index` to every cell of a spatial hash grid with square cells `cell_size` wide that
+    // this segment's bounds overlap.
+    pub fn insert_into_hash(self,
+                            cell_size: f32,
+                            index: usize,
+                            grid: &mut HashMap<(i32, i32), Vec<usize>>) {
+        let bounds = self.bounds();
+        let min = (bounds.origin() * (1.0 / cell_size)).floor().to_i32();
+        let max = (bounds.lower_right() * (1.0 / cell_size)).floor().to_i32();
+        for y in min.y()..=max.y() {
+            for x in min.x()..=max.x() {
+                grid.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    // If this segment crosses the horizontal line at `scanline_y`, returns the x coordinate of
+    // the crossing, the fraction of the pixel containing it that lies to the right of the
+    // crossing, and the segment's `y_winding()`. The segment's y range is treated as half-open,
+    // so that a scanline through a vertex shared by two segments is counted once.
+    pub fn subpixel_coverage_events(self, scanline_y: f32) -> Option<(f32, f32, i32)> {
+        if scanline_y < self.min_y() || scanline_y >= self.max_y() {
+            return None;
+        }
+        let x = self.solve_x_for_y(scanline_y);
+        Some((x, x.floor() + 1.0 - x, self.y_winding()))
+    }
+
+    // Returns this segment's signed crossing of the ray from `point` in the direction `ray_dir`:
+    // the `y_winding()` it would have in a frame rotated so that the ray points toward positive
+    // x, or 0 if it misses the ray. With `ray_dir` pointing toward positive x, summing this over
+    // a closed path gives `batch_winding()`; other directions let callers avoid rays through
+    // vertices. As there, the segment's extent across the ray is treated as half-open.
+    pub fn ray_winding_dir(self, point: Vector2F, ray_dir: Vector2F) -> i32 {
+        let side = |endpoint: Vector2F| ray_dir.det(endpoint - point);
+        let (from_side, to_side) = (side(self.from()), side(self.to()));
+        if f32::min(from_side, to_side) > 0.0 || f32::max(from_side, to_side) <= 0.0 {
+            return 0;
+        }
+        let crossing = self.sample(from_side / (from_side - to_side));
+        if ray_dir.dot(crossing - point) <= 0.0 {
+            return 0;
+        }
+        if to_side > from_side { 1 } else { -1 }
+    }
+
+    // Returns a value whose sign tells which side of this segment's line `point` lies on: positive
+    // for the inside, as used for clipping, and zero on the line. The magnitude is twice the area
+    // of the triangle formed by the segment and the point.
+    #[inline]
+    pub fn side_of_point(self, point: Vector2F) -> f32 {
+        self.vector().det(point - self.from())
+    }
+
+    // Returns 1 if `next` turns toward the positive side of this segment, per `side_of_point()`,
+    // -1 if it turns toward the negative side, and 0 if the two segments are parallel.
+    #[inline]
+    pub fn turn_sign(self, next: LineSegment2F) -> i32 {
+        let det = self.vector().det(next.vector());
+        if det > 0.0 {
+            1
+        } else if det < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    // Returns the signed angle from `from` to `to` as seen from `source`, in `(-π, π]`: positive
+    // if the segment sweeps counterclockwise in the math sense (clockwise with y pointing down).
+    #[inline]
+    pub fn subtended_angle(self, source: Vector2F) -> f32 {
+        let (from, to) = (self.from() - source, self.to() - source);
+        f32::atan2(from.det(to), from.dot(to))
+    }
+
+    // Returns the interval of angles about `source`, as `(start, end)` in radians, that this
+    // segment blocks, or `None` if the segment is seen edge-on from `source`. `start` is in
+    // `(-π, π]` and `end - start` is the segment's unsigned `subtended_angle()`, so `end` may
+    // exceed π for segments that straddle the negative x axis.
+    pub fn visibility_blocking_angles(self, source: Vector2F) -> Option<(f32, f32)> {
+        let (from, to) = (self.from() - source, self.to() - source);
+        if from.det(to) == 0.0 {
+            return None;
+        }
+        let angle = self.subtended_angle(source);
+        let start = if angle > 0.0 { from } else { to };
+        let start_angle = f32::atan2(start.y(), start.x());
+        Some((start_angle, start_angle + f32::abs(angle)))
+    }
+
+    // Returns the fraction of the parallelogram with the given corners, in winding order, that
+    // lies on the inside of this segment's line, per `side_of_point()`.
+    pub fn coverage_in_parallelogram(self, corners: [Vector2F; 4]) -> f32 {
+        let total_area = f32::abs(polygon_area(&corners));
+        if total_area == 0.0 {
+            return 0.0;
+        }
+        let clipped = self.clip_polygon_to_inside(&corners);
+        util::clamp(f32::abs(polygon_area(&clipped)) / total_area, 0.0, 1.0)
+    }
+
+    // Returns the fraction of a tent filter of the given radius, centered at `pixel_center`, that
+    // lies on the inside of this segment's line, per `side_of_point()`. The filter is the product
+    // of a triangular kernel along each axis, so its coverage is smoother across edges than that
+    // of a box filter. The coverage of each column is computed exactly, along whichever axis the
+    // line is shallower in, and the columns are summed with Simpson's rule.
+    pub fn tent_filter_coverage(self, pixel_center: Vector2F, radius: f32) -> f32 {
+        debug_assert!(radius > 0.0);
+        let vector = self.vector();
+        if vector.is_zero() {
+            return 0.0;
+        }
+
+        // Swapping the axes reverses which side of the line is inside.
+        let steep = f32::abs(vector.y()) > f32::abs(vector.x());
+        let (vector, from, center) = if steep {
+            (vector.yx(), self.from().yx(), pixel_center.yx())
+        } else {
+            (vector, self.from(), pixel_center)
+        };
+        let inside_above = (vector.x() > 0.0) != steep;
+        let slope = vector.y() / vector.x();
+
+        let tent = |u: f32| f32::max(1.0 - f32::abs(u) / radius, 0.0) / radius;
+        let tent_integral = |u: f32| {
+            let u = util::clamp(u / radius, -1.0, 1.0);
+            if u <= 0.0 { 0.5 * (1.0 + u) * (1.0 + u) } else { 1.0 - 0.5 * (1.0 - u) * (1.0 - u) }
+        };
+        let column_coverage = |u: f32| {
+            let boundary = from.y() + slope * (center.x() + u - from.x()) - center.y();
+            let below = tent_integral(boundary);
+            if inside_above { 1.0 - below } else { below }
+        };
+
+        // The kernel's kink at the center falls between panels, since the interval count is a
+        // multiple of 4.
+        let step = 2.0 * radius / TENT_FILTER_INTERVALS as f32;
+        let mut sum = 0.0;
+        for index in 0..=TENT_FILTER_INTERVALS {
+            let u = -radius + index as f32 * step;
+            let weight = if index == 0 || index == TENT_FILTER_INTERVALS {
+                1.0
+            } else if index % 2 == 1 {
+                4.0
+            } else {
+                2.0
+            };
+            sum += weight * tent(u) * column_coverage(u);
+        }
+        util::clamp(sum * step / 3.0, 0.0, 1.0)
+    }
+
+    // Returns the fraction of the ellipse that is the unit circle transformed by `axes` and
+    // centered at `center` that lies on the inside of this segment's line, per
+    // `side_of_point()`. The segment is mapped into the ellipse's local frame, where the ellipse
+    // is the unit circle and the covered area is a circular segment.
+    pub fn coverage_in_ellipse(self, center: Vector2F, axes: Matrix2x2F) -> f32 {
+        let det = axes.det();
+        if det == 0.0 || self.is_zero_length() {
+            return 0.0;
+        }
+
+        let inverse = axes.inverse();
+        let local = LineSegment2F::new(inverse * (self.from() - center),
+                                       inverse * (self.to() - center));
+
+        // Signed distance from the circle's center to the line, positive on the inside. A
+        // reflection in `axes` swaps the sides of the line.
+        let mut distance = local.side_of_point(Vector2F::zero()) / local.length();
+        if det < 0.0 {
+            distance = -distance;
+        }
+        let distance = util::clamp(distance, -1.0, 1.0);
+        let area = PI - f32::acos(distance) + distance * f32::sqrt(1.0 - distance * distance);
+        util::clamp(area / PI, 0.0, 1.0)
+    }
+
+    // Clips the convex polygon `points` to the inside of this segment's line.
+    fn clip_polygon_to_inside(self, points: &[Vector2F]) -> Vec<Vector2F> {
+        let mut clipped = vec![];
+        for (index, &point) in points.iter().enumerate() {
+            let next = points[(index + 1) % points.len()];
+            let (side, next_side) = (self.side_of_point(point), self.side_of_point(next));
+            if side >= 0.0 {
+                clipped.push(point);
+            }
+            if (side >= 0.0) != (next_side >= 0.0) {
+                clipped.push(point.lerp(next, side / (side - next_side)));
+            }
+        }
+        clipped
+    }
+
+    // Returns the triangle fanned from `anchor` to this segment, along with its winding: 1 or -1
+    // depending on the triangle's orientation, or 0 if it's degenerate. Additively blending the
+    // triangles of every segment of a closed path with their windings yields the path's winding
+    // number at every point, whatever the anchor. (The triangle's orientation, rather than
+    // `y_winding()`, is what makes the sum independent of the anchor.)
+    #[inline]
+    pub fn fill_fan_triangle(self, anchor: Vector2F) -> ([Vector2F; 3], i32) {
+        let area = (self.from() - anchor).det(self.to() - anchor);
+        let winding = if area > 0.0 { 1 } else if area < 0.0 { -1 } else { 0 };
+        ([anchor, self.from(), self.to()], winding)
+    }
+
+    // Returns the pieces of this segment that lie where the winding number of the closed path
+    // `path` is nonzero, ordered from `from` to `to`. Adjacent inside pieces are merged.
+    pub fn clip_to_nonzero_region(self, path: &[LineSegment2F]) -> Vec<LineSegment2F> {
+        let mut ts = vec![0.0, 1.0];
+        for &edge in path {
+            let (t, edge_t) = match (self.intersection_t(edge), edge.intersection_t(self)) {
+                (Some(t), Some(edge_t)) => (t, edge_t),
+                _ => continue,
+            };
+            if t > 0.0 && t < 1.0 && (0.0..=1.0).contains(&edge_t) {
+                ts.push(t);
+            }
+        }
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut pieces: Vec<LineSegment2F> = vec![];
+        let mut inside_start = None;
+        for pair in ts.windows(2) {
+            let inside = pair[1] > pair[0] &&
+                batch_winding(path, self.sample(0.5 * (pair[0] + pair[1]))) != 0;
+            match (inside, inside_start) {
+                (true, None) => inside_start = Some(pair[0]),
+                (false, Some(start)) => {
+                    pieces.push(self.slice(start, pair[0]));
+                    inside_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = inside_start {
+            pieces.push(self.slice(start, 1.0));
+        }
+        pieces
+    }
+
+    // Like `clip_to_nonzero_region()`, for a shape made of several closed contours. The winding
+    // numbers of all the contours are summed, so holes wound opposite to the outer contour are
+    // carved out of it.
+    pub fn clip_to_filled_shape(self, contours: &[Vec<LineSegment2F>]) -> Vec<LineSegment2F> {
+        let path: Vec<_> = contours.iter().flatten().copied().collect();
+        self.clip_to_nonzero_region(&path)
+    }
+
+    // Returns the signed fraction of `pixel` lying below this segment, within its x extent. The
+    // sign follows the segment's x direction, so that the coverage of a closed path is the sum of
+    // its segments' coverages. Shallow segments are integrated over x and steep ones over y, so
+    // that neither integration divides by a small extent.
+    pub fn exact_pixel_coverage(self, pixel: RectF) -> f32 {
+        let segment = match self.clip_to_x_strip(pixel.min_x(), pixel.max_x()) {
+            None => return 0.0,
+            Some(segment) => segment,
+        };
+        let vector = segment.vector();
+        let area = if f32::abs(vector.x()) >= f32::abs(vector.y()) {
+            segment.area_below_by_x(pixel)
+        } else {
+            segment.area_below_by_y(pixel)
+        };
+        area / (pixel.width() * pixel.height())
+    }
+
+    // Returns `exact_pixel_coverage()` for a sampling grid shifted by `jitter`, as used for
+    // temporal antialiasing; equivalently, the coverage of this segment shifted by `-jitter`.
+    #[inline]
+    pub fn coverage_with_jitter(self, pixel: RectF, jitter: Vector2F) -> f32 {
+        (self - jitter).exact_pixel_coverage(pixel)
+    }
+
+    // Returns the magnitude of `exact_pixel_coverage()`, raised to at least `min_alpha` if this
+    // segment passes through the interior of `pixel`, so that sub-pixel hairlines stay visible.
+    // Segments that only touch the pixel's boundary, or whose bounds merely overlap it, aren't
+    // raised.
+    pub fn min_visibility_coverage(self, pixel: RectF, min_alpha: f32) -> f32 {
+        let coverage = f32::abs(self.exact_pixel_coverage(pixel));
+        // The clipped piece is a chord of the pixel, so it passes through the interior unless its
+        // midpoint lies on the boundary.
+        let midpoint = match self.clip_to_rect(pixel) {
+            None => return coverage,
+            Some(piece) => piece.midpoint(),
+        };
+        if midpoint.x() > pixel.min_x() && midpoint.x() < pixel.max_x() &&
+                midpoint.y() > pixel.min_y() && midpoint.y() < pixel.max_y() {
+            f32::min(f32::max(coverage, min_alpha), 1.0)
+        } else {
+            coverage
+        }
+    }
+
+    // Integrates the area of `pixel` below this segment, which must lie within its x range, over
+    // x, by summing trapezoids between the points where the segment enters and leaves the pixel.
+    fn area_below_by_x(self, pixel: RectF) -> f32 {
+        let mut ts = vec![0.0, 1.0];
+        if self.from_y() != self.to_y() {
+            for &y in &[pixel.min_y(), pixel.max_y()] {
+                let t = self.solve_t_for_y(y);
+                if t > 0.0 && t < 1.0 {
+                    ts.push(t);
+                }
+            }
+        }
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let clamp_y = |point: Vector2F| util::clamp(point.y(), pixel.min_y(), pixel.max_y());
+        let mut area = 0.0;
+        for pair in ts.windows(2) {
+            let piece = self.slice(pair[0], pair[1]);
+            let mid_y = 0.5 * (clamp_y(piece.from()) + clamp_y(piece.to()));
+            area += (piece.to_x() - piece.from_x()) * (pixel.max_y() - mid_y);
+        }
+        area
+    }
+
+    // Integrates the same area as `area_below_by_x()` over y instead: the area is the full height
+    // of the pixel across the segment's x extent, less the portion above the segment, whose width
+    // at each y falls linearly from the full extent at the segment's top to zero at its bottom.
+    fn area_below_by_y(self, pixel: RectF) -> f32 {
+        let dx = self.to_x() - self.from_x();
+        let (min_y, max_y) = (self.min_y(), self.max_y());
+        let clamp_y = |y: f32| util::clamp(y, pixel.min_y(), pixel.max_y());
+        let (top, bottom) = (clamp_y(min_y), clamp_y(max_y));
+        let area_above = dx * (top - pixel.min_y()) +
+            dx / (max_y - min_y) * (max_y * (bottom - top) - 0.5 * (bottom * bottom - top * top));
+        dx * pixel.height() - area_above
+    }
+
+    // Returns the portion of this segment between the parameters `t0` and `t1`, clamped to the
+    // segment. Endpoints that aren't cut off are returned unchanged.
+    #[inline]
+    fn slice(self, t0: f32, t1: f32) -> LineSegment2F {
+        let from = if t0 <= 0.0 { self.from() } else { self.sample(t0) };
+        let to = if t1 >= 1.0 { self.to() } else { self.sample(t1) };
+        LineSegment2F::new(from, to)
+    }
+}
+
+// Returns the winding number of the closed path `segments` around `point`, counting the
+// `y_winding()` of every segment crossed by a ray from `point` toward positive x. Each segment's
+// y range is treated as half-open, so vertices on the ray are counted once.
+pub fn batch_winding(segments: &[LineSegment2F], point: Vector2F) -> i32 {
+    let mut winding = 0;
+    for &segment in segments {
+        if point.y() >= segment.min_y() && point.y() < segment.max_y() &&
+                segment.solve_x_for_y(point.y()) > point.x() {
+            winding += segment.y_winding();
+        }
+    }
+    winding
+}
+
+// Maps a pixel's accumulated linear coverage, with either winding, to the value to use for
+// blending in a space with the given gamma, such as 2.2 for sRGB. Coverage only sums correctly in
+// linear space, so it should be accumulated with `LineSegment2F::accumulate_into_scanline()` and
+// mapped through the gamma curve only once each pixel's sum is final.
+#[inline]
+pub fn resolve_gamma_coverage(coverage: f32, gamma: f32) -> f32 {
+    f32::min(f32::abs(coverage), 1.0).powf(1.0 / gamma)
+}
+
+// Quantizes `coverage`, in `[0, 1]`, to 8 bits with ordered dithering: the scaled value is rounded
+// up when its fractional part is at least `1 - threshold`, where the threshold comes from the
+// entry of the row-major Bayer `matrix`, any square size such as 4×4 or 8×8, for `pixel`. A given
+// pixel always gets the same threshold, so the result is stable from frame to frame, and the
+// average over the matrix approximates the exact value. An empty `matrix` disables dithering, so
+// the value is rounded to the nearest level.
+pub fn quantize_coverage_dithered(coverage: f32, pixel: (u32, u32), matrix: &[u8]) -> u8 {
+    if matrix.is_empty() {
+        return util::clamp((coverage * 255.0).round(), 0.0, 255.0) as u8;
+    }
+    let size = f32::sqrt(matrix.len() as f32) as usize;
+    debug_assert_eq!(size * size, matrix.len());
+    let entry = matrix[(pixel.1 as usize % size) * size + pixel.0 as usize % size];
+    let threshold = (entry as f32 + 0.5) / matrix.len() as f32;
+    util::clamp((coverage * 255.0 + threshold).floor(), 0.0, 255.0) as u8
+}
+
+// Returns true if `mid` lies within `tolerance` of the segment from `prev` to `next`, so that it
+// can be dropped while decimating a polyline one vertex at a time.
+#[inline]
+pub fn is_vertex_redundant(prev: Vector2F, mid: Vector2F, next: Vector2F, tolerance: f32) -> bool {
+    LineSegment2F::new(prev, next).distance_to_point(mid) < tolerance
+}
+
+// Returns `count` segments radiating from `inner_radius` to `outer_radius` around `center`,
+// evenly spaced over a full turn starting at `start_angle`.
+pub fn radial_spokes(center: Vector2F,
+                     inner_radius: f32,
+                     outer_radius: f32,
+                     count: usize,
+                     start_angle: f32)
+                     -> Vec<LineSegment2F> {
+    let step = 2.0 * PI / count as f32;
+    (0..count).map(|index| {
+        // Compute each angle directly rather than accumulating the step, to avoid drift.
+        let angle = start_angle + index as f32 * step;
+        let direction = vec2f(angle.cos(), angle.sin());
+        LineSegment2F::new(center + direction * inner_radius, center + direction * outer_radius)
+    }).collect()
+}
+
+// Appends the coordinates of `segments` to four parallel arrays, one per coordinate. Segments
+// are transposed four at a time.
+pub fn to_soa(segments: &[LineSegment2F],
+              from_x: &mut Vec<f32>,
+              from_y: &mut Vec<f32>,
+              to_x: &mut Vec<f32>,
+              to_y: &mut Vec<f32>) {
+    fn push_lanes(values: &mut Vec<f32>, lanes: F32x4) {
+        values.extend_from_slice(&[lanes[0], lanes[1], lanes[2], lanes[3]]);
+    }
+
+    let mut chunks = segments.chunks_exact(4);
+    for chunk in &mut chunks {
+        let (a, b, c, d) = (chunk[0].0, chunk[1].0, chunk[2].0, chunk[3].0);
+        let (from_ab, from_cd) = (a.concat_xy_xy(b).xzyw(), c.concat_xy_xy(d).xzyw());
+        let (to_ab, to_cd) = (a.concat_zw_zw(b).xzyw(), c.concat_zw_zw(d).xzyw());
+        push_lanes(from_x, from_ab.concat_xy_xy(from_cd));
+        push_lanes(from_y, from_ab.concat_zw_zw(from_cd));
+        push_lanes(to_x, to_ab.concat_xy_xy(to_cd));
+        push_lanes(to_y, to_ab.concat_zw_zw(to_cd));
+    }
+    for segment in chunks.remainder() {
+        from_x.push(segment.from_x());
+        from_y.push(segment.from_y());
+        to_x.push(segment.to_x());
+        to_y.push(segment.to_y());
+    }
+}
+
+// Builds segments from four parallel coordinate arrays, as produced by `to_soa()`. Segments are
+// transposed four at a time. Panics if the arrays' lengths differ.
+pub fn from_soa(from_x: &[f32], from_y: &[f32], to_x: &[f32], to_y: &[f32])
+                -> Vec<LineSegment2F> {
+    let count = from_x.len();
+    assert!(from_y.len() == count && to_x.len() == count && to_y.len() == count,
+            "structure-of-arrays coordinate arrays must have the same length");
+
+    let mut segments = Vec::with_capacity(count);
+    let lanes = |values: &[f32], index: usize| {
+        F32x4::new(values[index], values[index + 1], values[index + 2], values[index + 3])
+    };
+    let simd_count = count - count % 4;
+    for index in (0..simd_count).step_by(4) {
+        let (xs, ys) = (lanes(from_x, index), lanes(from_y, index));
+        let (to_xs, to_ys) = (lanes(to_x, index), lanes(to_y, index));
+        let (from_ab, from_cd) = (xs.concat_xy_xy(ys).xzyw(), xs.concat_zw_zw(ys).xzyw());
+        let (to_ab, to_cd) = (to_xs.concat_xy_xy(to_ys).xzyw(), to_xs.concat_zw_zw(to_ys).xzyw());
+        segments.push(LineSegment2F(from_ab.concat_xy_xy(to_ab)));
+        segments.push(LineSegment2F(from_ab.concat_zw_zw(to_ab)));
+        segments.push(LineSegment2F(from_cd.concat_xy_xy(to_cd)));
+        segments.push(LineSegment2F(from_cd.concat_zw_zw(to_cd)));
+    }
+    for index in simd_count..count {
+        segments.push(LineSegment2F::new(vec2f(from_x[index], from_y[index]),
+                                         vec2f(to_x[index], to_y[index])));
+    }
+    segments
+}
+
+// Returns a unit tangent at each of `points`: the normalized average of the directions of the
+// segments entering and leaving it. The ends of open polylines use the one segment they have.
+// Where the polyline doubles back, the leaving direction is used.
+pub fn estimate_tangents(points: &[Vector2F], closed: bool) -> Vec<Vector2F> {
+    let point_count = points.len();
+    let direction = |from: usize, to: usize| {
+        LineSegment2F::new(points[from], points[to]).direction()
+    };
+    (0..point_count).map(|index| {
+        let incoming = if index > 0 {
+            direction(index - 1, index)
+        } else if closed && point_count > 1 {
+            direction(point_count - 1, 0)
+        } else {
+            Vector2F::zero()
+        };
+        let outgoing = if index + 1 < point_count {
+            direction(index, index + 1)
+        } else if closed && point_count > 1 {
+            direction(index, 0)
+        } else {
+            Vector2F::zero()
+        };
+
+        let sum = incoming + outgoing;
+        if sum.square_length() > util::EPSILON * util::EPSILON {
+            sum.normalize()
+        } else if !outgoing.is_zero() {
+            outgoing
+        } else {
+            incoming
+        }
+    }).collect()
+}
+
+// Returns every crossing between two segments of `segments` as `(i, t_i, j, t_j)`, where `i < j`
+// and `t_i` and `t_j` parameterize the crossing along `segments[i]` and `segments[j]`. The
+// segments are treated as a closed contour, so consecutive segments, including the last and the
+// first, are assumed to be joined and aren't tested against each other.
+pub fn self_intersections(segments: &[LineSegment2F]) -> Vec<(usize, f32, usize, f32)> {
+    let segment_count = segments.len();
+    let mut intersections = vec![];
+    for i in 0..segment_count {
+        for j in (i + 2)..segment_count {
+            if i == 0 && j == segment_count - 1 {
+                continue;
+            }
+            let (a, b) = (segments[i], segments[j]);
+            if !a.bounds().intersects(b.bounds()) {
+                continue;
+            }
+            if let (Some(t_a), Some(t_b)) = (a.intersection_t(b), b.intersection_t(a)) {
+                if t_a > 0.0 && t_a < 1.0 && t_b > 0.0 && t_b < 1.0 {
+                    intersections.push((i, t_a, j, t_b));
+                }
+            }
+        }
+    }
+    intersections
+}
+
+// Offsets the closed contour `segments` by `distance`, toward the side of each segment that
+// `side_of_point()` considers inside for positive distances, and removes the loops that the
+// offset folds into itself. Offset segments are joined at the intersections of their lines. The
+// offset contour is cut apart at its self-intersections into simple loops, and the loops wound
+// opposite to the original contour, which the offset has turned inside out, are discarded. An
+// offset that pinches the shape in two returns each part as a separate contour.
+pub fn offset_with_loop_removal(segments: &[LineSegment2F], distance: f32)
+                                -> Vec<Vec<LineSegment2F>> {
+    let segment_count = segments.len();
+    if segment_count < 2 {
+        return vec![];
+    }
+
+    let offset_segments: Vec<_> = segments.iter().map(|segment| segment.offset(distance)).collect();
+    let joins: Vec<Vector2F> = (0..segment_count).map(|index| {
+        let prev = offset_segments[(index + segment_count - 1) % segment_count];
+        let next = offset_segments[index];
+        match prev.intersection_t(next) {
+            Some(t) => prev.sample(t),
+            None => next.from(),
+        }
+    }).collect();
+    let contour: Vec<_> = (0..segment_count).map(|index| {
+        LineSegment2F::new(joins[index], joins[(index + 1) % segment_count])
+    }).collect();
+
+    // Walk the contour, recording each point along with the crossing, if any, it lies on.
+    let mut crossings: Vec<Vec<(f32, usize)>> = vec![vec![]; segment_count];
+    for (crossing, &(i, t_i, j, t_j)) in self_intersections(&contour).iter().enumerate() {
+        crossings[i].push((t_i, crossing));
+        crossings[j].push((t_j, crossing));
+    }
+    let mut walk = vec![];
+    for (segment, crossings) in contour.iter().zip(crossings.iter_mut()) {
+        walk.push((segment.from(), None));
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        walk.extend(crossings.iter().map(|&(t, crossing)| (segment.sample(t), Some(crossing))));
+    }
+
+    // Every time the walk returns to a crossing, the points since its first visit form a loop.
+    let mut loops = vec![];
+    let mut stack: Vec<(Vector2F, Option<usize>)> = vec![];
+    for (point, crossing) in walk {
+        let first_visit = crossing.and_then(|crossing| {
+            stack.iter().position(|&(_, other)| other == Some(crossing))
+        });
+        match first_visit {
+            Some(index) => {
+                let points: Vec<_> = stack.drain((index + 1)..).map(|(point, _)| point).collect();
+                let mut points_in_loop = vec![stack[index].0];
+                points_in_loop.extend(points);
+                loops.push(points_in_loop);
+                stack[index].1 = None;
+            }
+            None => stack.push((point, crossing)),
+        }
+    }
+    loops.push(stack.into_iter().map(|(point, _)| point).collect());
+
+    let points: Vec<_> = segments.iter().map(|segment| segment.from()).collect();
+    let original_area = polygon_area(&points);
+    loops.into_iter().filter(|points| {
+        let area = polygon_area(points);
+        area != 0.0 && (area > 0.0) == (original_area > 0.0)
+    }).map(|points| {
+        let point_count = points.len();
+        (0..point_count).map(|index| {
+            LineSegment2F::new(points[index], points[(index + 1) % point_count])
+        }).filter(|segment| !segment.is_zero_length()).collect()
+    }).collect()
+}
+
+// Returns the start, control, and end points of a quadratic Bézier curve approximating the
+// polyline through `points`, or `None` if no single quadratic fits it within `tolerance`.
+//
+// The curve keeps the polyline's endpoints, and its control point is a least-squares fit of the
+// interior points, starting from their chord-length parameters. The fit is accepted only if every
+// point lies within `tolerance` of the sampled curve and every curve sample lies within
+// `tolerance` of the polyline.
+pub fn fit_quadratic(points: &[Vector2F], tolerance: f32)
+                     -> Option<(Vector2F, Vector2F, Vector2F)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let (start, end) = (points[0], points[points.len() - 1]);
+    let polyline: Vec<_> = points.windows(2).map(|pair| LineSegment2F::new(pair[0], pair[1]))
+                                            .collect();
+    let total_length: f32 = polyline.iter().map(|segment| segment.length()).sum();
+    let evaluate = |ctrl: Vector2F, t: f32| start.lerp(ctrl, t).lerp(ctrl.lerp(end, t), t);
+
+    // Start from the chord-length parameterization of the points.
+    let mut distance = 0.0;
+    let mut params = vec![0.0];
+    for segment in &polyline {
+        distance += segment.length();
+        params.push(if total_length > 0.0 { distance / total_length } else { 0.0 });
+    }
+
+    // Alternate a least-squares fit of the control point, where
+    // B(t) = (1 - t)² start + 2t(1 - t) ctrl + t² end, with a Newton step that moves each
+    // parameter toward the closest point on the curve.
+    let mut ctrl = start.lerp(end, 0.5);
+    for _ in 0..REPARAMETERIZATION_ITERATIONS {
+        let (mut numerator, mut denominator) = (Vector2F::zero(), 0.0);
+        for (&point, &t) in points.iter().zip(params.iter()) {
+            let weight = 2.0 * t * (1.0 - t);
+            numerator += (point - start * ((1.0 - t) * (1.0 - t)) - end * (t * t)) * weight;
+            denominator += weight * weight;
+        }
+        if denominator > 0.0 {
+            ctrl = numerator * (1.0 / denominator);
+        }
+
+        let second_derivative = (start - ctrl * 2.0 + end) * 2.0;
+        for (&point, t) in points.iter().zip(params.iter_mut()).skip(1) {
+            let error = evaluate(ctrl, *t) - point;
+            let derivative = (ctrl - start).lerp(end - ctrl, *t) * 2.0;
+            let slope = derivative.square_length() + error.dot(second_derivative);
+            if slope != 0.0 {
+                *t = util::clamp(*t - error.dot(derivative) / slope, 0.0, 1.0);
+            }
+        }
+    }
+
+    let sample_count = usize::max(16, points.len() * 4);
+    let mut curve = Vec::with_capacity(sample_count);
+    let mut from = start;
+    for step in 1..=sample_count {
+        let t = step as f32 / sample_count as f32;
+        let to = evaluate(ctrl, t);
+        curve.push(LineSegment2F::new(from, to));
+        from = to;
+    }
+
+    let within = |segments: &[LineSegment2F], point: Vector2F| {
+        segments.iter().any(|segment| segment.distance_to_point(point) <= tolerance)
+    };
+    if points.iter().all(|&point| within(&curve, point)) &&
+            curve.iter().all(|segment| within(&polyline, segment.to())) {
+        Some((start, ctrl, end))
+    } else {
+        None
+    }
+}
+
+// Returns the arc of the circle through `a`, `b`, and `c` that starts at `a`, passes through `b`,
+// and ends at `c`, flattened so that no chord deviates from the arc by more than `tolerance`,
+// which must be positive and is raised to at least `util::EPSILON`. At most `MAX_ARC_SEGMENTS`
+// segments are returned. If the points are collinear, returns `None`.
+pub fn arc_through_three_points(a: Vector2F, b: Vector2F, c: Vector2F, tolerance: f32)
+                                -> Option<Vec<LineSegment2F>> {
+    let (ab, ac) = (b - a, c - a);
+    let det = ab.det(ac);
+    if f32::abs(det) <= util::EPSILON * ab.length() * ac.length() {
+        return None;
+    }
+
+    // Circumcircle.
+    let (ab_square_length, ac_square_length) = (ab.square_length(), ac.square_length());
+    let center = a + vec2f(ac.y() * ab_square_length - ab.y() * ac_square_length,
+                           ab.x() * ac_square_length - ac.x() * ab_square_length) * (0.5 / det);
+    let radius = (a - center).length();
+
+    // Sweep from `a` to `c` in the direction that passes through `b`.
+    let angle_of = |point: Vector2F| f32::atan2(point.y() - center.y(), point.x() - center.x());
+    let start_angle = angle_of(a);
+    let mut sweep_angle = angle_of(c) - start_angle;
+    if det > 0.0 && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    } else if det < 0.0 && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    }
+
+    // The largest step whose chord stays within the tolerance of the arc is twice
+    // `acos(1 - tolerance / radius)`. When the radius is large, that cosine rounds to 1, so the
+    // angle is found from its sine instead, in double precision.
+    debug_assert!(tolerance > 0.0);
+    let tolerance = f32::max(tolerance, util::EPSILON) as f64;
+    let radius_f64 = radius as f64;
+    let max_step = 2.0 * if tolerance < radius_f64 {
+        f64::asin(f64::sqrt(tolerance * (2.0 * radius_f64 - tolerance)) / radius_f64)
+    } else {
+        f64::acos(f64::max(1.0 - tolerance / radius_f64, -1.0))
+    };
+    let count = if max_step > 0.0 {
+        let count = f64::ceil(f32::abs(sweep_angle) as f64 / max_step);
+        f64::min(f64::max(count, 1.0), MAX_ARC_SEGMENTS as f64) as usize
+    } else {
+        MAX_ARC_SEGMENTS
+    };
+    let mut segments = Vec::with_capacity(count);
+    let mut from = a;
+    for step in 1..=count {
+        let to = if step == count {
+            c
+        } else {
+            let angle = start_angle + sweep_angle * step as f32 / count as f32;
+            center + vec2f(angle.cos(), angle.sin()) * radius
+        };
+        segments.push(LineSegment2F::new(from, to));
+        from = to;
+    }
+    Some(segments)
+}
+
+// Returns the centers of the circles of the given radius tangent to the lines through both
+// `incoming` and `outgoing`, which lie on the bisector of the corner that the lines form where
+// they meet. The first center is inside the corner, where `fillet()` places its arc; the second
+// is across the corner from it. Returns no centers if the segments are parallel or either is
+// zero-length.
+pub fn tangent_circles(incoming: LineSegment2F, outgoing: LineSegment2F, radius: f32)
+                       -> ArrayVec<[Vector2F; 2]> {
+    let mut centers = ArrayVec::new();
+    let (back_direction, out_direction) = (-incoming.direction(), outgoing.direction());
+    let sin_turn = back_direction.det(out_direction);
+    if back_direction.is_zero() || out_direction.is_zero() || f32::abs(sin_turn) <= util::EPSILON {
+        return centers;
+    }
+    let corner = match incoming.intersection_t(outgoing) {
+        None => return centers,
+        Some(t) => incoming.sample(t),
+    };
+
+    // The center lies `radius / sin(θ / 2)` along the bisector, for a corner angle θ.
+    let bisector = (back_direction + out_direction).normalize();
+    let distance = radius / f32::abs(back_direction.det(bisector));
+    centers.push(corner + bisector * distance);
+    centers.push(corner - bisector * distance);
+    centers
+}
+
+// Returns the arc of the given radius tangent to both `incoming` and `outgoing`, which must share
+// the vertex `incoming.to()`, flattened under `tolerance`. The arc replaces the corner, so it
+// starts on `incoming` and ends on `outgoing`. Returns an empty vector if the segments are
+// collinear or either is zero-length.
+pub fn fillet(incoming: LineSegment2F, outgoing: LineSegment2F, radius: f32, tolerance: f32)
+              -> Vec<LineSegment2F> {
+    if incoming.is_zero_length() || outgoing.is_zero_length() || radius <= 0.0 {
+        return vec![];
+    }
+    let (in_direction, out_direction) = (incoming.vector().normalize(),
+                                         outgoing.vector().normalize());
+    let (sin_turn, cos_turn) = (in_direction.det(out_direction), in_direction.dot(out_direction));
+    if f32::abs(sin_turn) <= util::EPSILON {
+        return vec![];
+    }
+
+    // Distance from the vertex to the tangent points: `radius * tan(turn / 2)`.
+    let trim = radius * f32::abs(sin_turn) / (1.0 + cos_turn);
+    let vertex = incoming.to();
+    let (start, end) = (vertex - in_direction * trim, vertex + out_direction * trim);
+    let inside_normal = vec2f(-in_direction.y(), in_direction.x()) * sin_turn.signum();
+    let center = start + inside_normal * radius;
+    let mid = center + (vertex - center).normalize() * radius;
+    arc_through_three_points(start, mid, end, tolerance)
+        .unwrap_or_else(|| vec![LineSegment2F::new(start, end)])
+}
+
+// Returns the polyline through `points` with each interior vertex, and every vertex if `closed`,
+// replaced by a `fillet()` of the given radius. The radius is reduced at corners where the
+// fillet would consume more than half of either adjacent edge, so neighboring fillets never
+// overlap.
+pub fn round_corners(points: &[Vector2F], radius: f32, tolerance: f32, closed: bool)
+                     -> Vec<LineSegment2F> {
+    let point_count = points.len();
+    if point_count < 2 {
+        return vec![];
+    }
+
+    let arcs: Vec<Vec<LineSegment2F>> = (0..point_count).map(|index| {
+        if !closed && (index == 0 || index == point_count - 1) {
+            return vec![];
+        }
+        let prev = points[(index + point_count - 1) % point_count];
+        let next = points[(index + 1) % point_count];
+        let incoming = LineSegment2F::new(prev, points[index]);
+        let outgoing = LineSegment2F::new(points[index], next);
+        if incoming.is_zero_length() || outgoing.is_zero_length() {
+            return vec![];
+        }
+
+        // Clamp the radius so that the trim, `radius * tan(turn / 2)`, fits in half of the
+        // shorter edge.
+        let (in_direction, out_direction) = (incoming.vector().normalize(),
+                                             outgoing.vector().normalize());
+        let tan_half_turn = f32::abs(in_direction.det(out_direction)) /
+            (1.0 + in_direction.dot(out_direction));
+        let max_trim = 0.5 * f32::min(incoming.length(), outgoing.length());
+        let radius = f32::min(radius, max_trim / tan_half_turn);
+        fillet(incoming, outgoing, radius, tolerance)
+    }).collect();
+
+    let entry = |index: usize| arcs[index].first().map_or(points[index], |arc| arc.from());
+    let exit = |index: usize| arcs[index].last().map_or(points[index], |arc| arc.to());
+
+    let mut segments = vec![];
+    let edge_count = if closed { point_count } else { point_count - 1 };
+    for index in 0..edge_count {
+        let next_index = (index + 1) % point_count;
+        let edge = LineSegment2F::new(exit(index), entry(next_index));
+        if !edge.is_zero_length() {
+            segments.push(edge);
+        }
+        segments.extend_from_slice(&arcs[next_index]);
+    }
+    segments
+}
+
+// Returns true if the edge `shared` between the triangles it forms with `p` and with `q` should be
+// flipped to the diagonal from `p` to `q` to satisfy the Delaunay condition: that is, if the
+// quadrilateral is strictly convex and `q` lies inside the circumcircle of `shared` and `p`.
+// Constrained edges are never flipped. The predicates are evaluated in double precision.
+pub fn should_flip(shared: LineSegment2F, p: Vector2F, q: Vector2F, constrained: bool) -> bool {
+    if constrained {
+        return false;
+    }
+
+    let (a, b) = (shared.from(), shared.to());
+    let orient = |a: Vector2F, b: Vector2F, c: Vector2F| {
+        let (ab_x, ab_y) = (b.x() as f64 - a.x() as f64, b.y() as f64 - a.y() as f64);
+        let (ac_x, ac_y) = (c.x() as f64 - a.x() as f64, c.y() as f64 - a.y() as f64);
+        ab_x * ac_y - ab_y * ac_x
+    };
+
+    // The new diagonal must lie inside the quadrilateral.
+    let (p_side, q_side) = (orient(a, b, p), orient(a, b, q));
+    if p_side * q_side >= 0.0 || orient(p, q, a) * orient(p, q, b) >= 0.0 {
+        return false;
+    }
+
+    let lift = |point: Vector2F| {
+        let (x, y) = (point.x() as f64 - q.x() as f64, point.y() as f64 - q.y() as f64);
+        (x, y, x * x + y * y)
+    };
+    let ((ax, ay, a2), (bx, by, b2), (px, py, p2)) = (lift(a), lift(b), lift(p));
+    let in_circle = ax * (by * p2 - b2 * py) - ay * (bx * p2 - b2 * px) + a2 * (bx * py - by * px);
+    in_circle * p_side > 0.0
+}
+
+// Returns true if the vertex shared by `incoming` and `outgoing` is strictly convex in a polygon
+// with the given winding. A polygon is counterclockwise if its signed area is positive, which is
+// clockwise on screen when y points down.
+#[inline]
+pub fn vertex_convexity(incoming: LineSegment2F, outgoing: LineSegment2F, polygon_ccw: bool)
+                        -> bool {
+    incoming.turn_sign(outgoing) == if polygon_ccw { 1 } else { -1 }
+}
+
+// Returns true if `p` lies inside the triangle `a`, `b`, `c`, of either winding. Points within
+// `util::EPSILON` of an edge count as inside.
+pub fn point_in_triangle(p: Vector2F, a: Vector2F, b: Vector2F, c: Vector2F) -> bool {
+    let edges = [LineSegment2F::new(a, b), LineSegment2F::new(b, c), LineSegment2F::new(c, a)];
+    let distances = edges.iter().map(|edge| edge.side_of_point(p) / edge.length());
+    let (mut min_distance, mut max_distance) = (f32::INFINITY, -f32::INFINITY);
+    for distance in distances {
+        // Zero-length edges of degenerate triangles have nothing to say.
+        if distance.is_nan() {
+            continue;
+        }
+        min_distance = f32::min(min_distance, distance);
+        max_distance = f32::max(max_distance, distance);
+    }
+    if min_distance > max_distance {
+        return false;
+    }
+    min_distance >= -util::EPSILON || max_distance <= util::EPSILON
+}
+
+// Returns the signed area enclosed by the closed contour `contour`, via the shoelace formula
+// over its segments.
+pub fn signed_area(contour: &[LineSegment2F]) -> f32 {
+    0.5 * contour.iter().map(|segment| segment.from().det(segment.to())).sum::<f32>()
+}
+
+#[inline]
+pub fn total_length(contour: &[LineSegment2F]) -> f32 {
+    contour.iter().map(|segment| segment.length()).sum()
+}
+
+// Returns the isoperimetric quotient of the closed contour `contour`, `4π * area / perimeter²`:
+// 1 for a circle, and smaller the more elongated or irregular the shape. Returns 0 if the
+// perimeter is zero.
+pub fn compactness(contour: &[LineSegment2F]) -> f32 {
+    let perimeter = total_length(contour);
+    if perimeter == 0.0 {
+        return 0.0;
+    }
+    4.0 * PI * f32::abs(signed_area(contour)) / (perimeter * perimeter)
+}
+
+// Returns the area-weighted centroid of the closed contour `contour`. Areas are signed, so
+// contours concatenated with opposite windings, such as an outline and its holes, combine
+// correctly. Returns `None` if the enclosed area is negligible relative to the perimeter.
+pub fn area_centroid(contour: &[LineSegment2F]) -> Option<Vector2F> {
+    let (mut twice_area, mut weighted_sum) = (0.0, Vector2F::zero());
+    for segment in contour {
+        let cross = segment.from().det(segment.to());
+        twice_area += cross;
+        weighted_sum += (segment.from() + segment.to()) * cross;
+    }
+    let perimeter = total_length(contour);
+    if f32::abs(twice_area) <= util::EPSILON * perimeter * perimeter {
+        return None;
+    }
+    Some(weighted_sum * (1.0 / (3.0 * twice_area)))
+}
+
+// Returns the signed area of the closed polygon `points`, with the same sign as the shoelace
+// formula, computed exactly for the coordinates rounded to a grid of `scale` units per unit. The
+// coordinates are promoted to integers and their cross products summed in 128 bits, so the
+// result doesn't depend on the order of summation. Rounding moves each point by up to
+// `0.5 / scale` along each axis; coordinates times `scale` must fit in an `i64`.
+pub fn exact_polygon_area(points: &[Vector2F], scale: f32) -> f64 {
+    let to_grid = |point: Vector2F| {
+        ((point.x() as f64 * scale as f64).round() as i64,
+         (point.y() as f64 * scale as f64).round() as i64)
+    };
+    let mut twice_area: i128 = 0;
+    for (index, &point) in points.iter().enumerate() {
+        let (x0, y0) = to_grid(point);
+        let (x1, y1) = to_grid(points[(index + 1) % points.len()]);
+        twice_area += x0 as i128 * y1 as i128 - x1 as i128 * y0 as i128;
+    }
+    twice_area as f64 * 0.5 / (scale as f64 * scale as f64)
+}
+
+// Returns the outline of an SVG 2 `miter-clip` join between `incoming` and `outgoing`, which must
+// share the vertex `incoming.to()`, for a stroke of the given half width. The outline starts at
+// the shared vertex, followed by the outer offset endpoint of `incoming`, then the miter tip, and
+// closes on the outer offset endpoint of `outgoing`. If the miter would extend further than
+// `miter_limit * half_width` from the vertex, the tip is replaced by the two points where the
+// miter crosses the line perpendicular to the bisector at that distance.
+pub fn miter_clip_join(incoming: LineSegment2F,
+                       outgoing: LineSegment2F,
+                       half_width: f32,
+                       miter_limit: f32)
+                       -> Vec<Vector2F> {
+    let vertex = incoming.to();
+    let (in_normal, out_normal) = match outer_join_normals(incoming, outgoing) {
+        None => return vec![vertex],
+        Some(normals) => normals,
+    };
+    let (in_point, out_point) = (vertex + in_normal * half_width, vertex + out_normal * half_width);
+
+    // The bisector points out of the corner. If the segments double back on each other, it
+    // continues along the incoming segment.
+    let in_direction = incoming.vector().normalize();
+    let bisector = in_normal + out_normal;
+    let bisector = if bisector.is_zero() { in_direction } else { bisector.normalize() };
+
+    let limit = miter_limit * half_width;
+    let cos_half_angle = in_normal.dot(bisector);
+    if cos_half_angle * limit >= half_width {
+        return vec![vertex, in_point, vertex + bisector * (half_width / cos_half_angle), out_point];
+    }
+
+    // Both offset lines reach the clip line at the same distance, by symmetry.
+    let distance = (limit - (in_point - vertex).dot(bisector)) / in_direction.dot(bisector);
+    let out_direction = outgoing.vector().normalize();
+    vec![vertex, in_point, in_point + in_direction * distance, out_point - out_direction * distance,
+         out_point]
+}
+
+// Returns the triangle of a bevel join between `incoming` and `outgoing`, which must share the
+// vertex `incoming.to()`, for a stroke of the given half width: the shared vertex, followed by
+// the outer offset endpoints of `incoming` and `outgoing`, as in `miter_clip_join()`. If either
+// segment is zero-length, the triangle collapses to the vertex.
+pub fn bevel_join_points(incoming: LineSegment2F, outgoing: LineSegment2F, half_width: f32)
+                         -> [Vector2F; 3] {
+    let vertex = incoming.to();
+    match outer_join_normals(incoming, outgoing) {
+        None => [vertex; 3],
+        Some((in_normal, out_normal)) => {
+            [vertex, vertex + in_normal * half_width, vertex + out_normal * half_width]
+        }
+    }
+}
+
+// Returns the unit normals of `incoming` and `outgoing` that point to the outside of the corner
+// at their shared vertex, or `None` if either segment is zero-length.
+fn outer_join_normals(incoming: LineSegment2F, outgoing: LineSegment2F)
+                      -> Option<(Vector2F, Vector2F)> {
+    if incoming.is_zero_length() || outgoing.is_zero_length() {
+        return None;
+    }
+    let (in_vector, out_vector) = (incoming.vector(), outgoing.vector());
+    let side = if in_vector.det(out_vector) > 0.0 { 1.0 } else { -1.0 };
+    let normal = |vector: Vector2F| vec2f(vector.y(), -vector.x()).normalize() * side;
+    Some((normal(in_vector), normal(out_vector)))
+}
+
+// Returns the signed area of the closed polygon `points`, via the shoelace formula.
+fn polygon_area(points: &[Vector2F]) -> f32 {
+    let mut area = 0.0;
+    for (index, &point) in points.iter().enumerate() {
+        area += point.det(points[(index + 1) % points.len()]);
+    }
+    area * 0.5
+}
+
+// Converts `value` to the bits of the nearest half-precision float, as in
+// `LineSegment2F::to_f16()`.
+fn f32_to_f16(value: f32) -> u16 {
+    let sign = ((value.to_bits() >> 16) & 0x8000) as u16;
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+
+    let bits = f32::min(f32::abs(value), F16_MAX).to_bits();
+    let exponent = (bits >> 23) as i32 - 127;
+    let mantissa = bits & 0x007f_ffff;
+    if exponent < -25 {
+        sign
+    } else if exponent < -14 {
+        // Subnormal, in units of 2⁻²⁴. Rounding up may carry into the smallest normal.
+        sign | round_shift_right(mantissa | 0x0080_0000, (-exponent - 1) as u32) as u16
+    } else {
+        // Rounding up may carry into the exponent, which the clamp keeps finite.
+        let half = (((exponent + 15) as u32) << 23) | mantissa;
+        sign | round_shift_right(half, 13) as u16
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits & 0x8000) as u32) << 16;
+    let (exponent, mantissa) = (((bits >> 10) & 0x1f) as u32, (bits & 0x03ff) as u32);
+    match exponent {
+        0 => {
+            let value = mantissa as f32 * (1.0 / (1 << 24) as f32);
+            f32::from_bits(sign | value.to_bits())
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(sign | ((exponent + 127 - 15) << 23) | (mantissa << 13)),
+    }
+}
+
+// Shifts `value` right by `shift` bits, rounding to nearest even.
+#[inline]
+fn round_shift_right(value: u32, shift: u32) -> u32 {
+    let (half, remainder) = (1 << (shift - 1), value & ((1 << shift) - 1));
+    let result = value >> shift;
+    if remainder > half || (remainder == half && result & 1 != 0) {
+        result + 1
+    } else {
+        result
+    }
+}
+
+type GridCell = (i64, i64);
+
+// Returns the cell of the grid with `util::EPSILON` spacing that `point` rounds to. The cell is
+// computed in double precision with 64-bit coordinates, so that distinct points far from the
+// origin don't saturate to the same cell.
+fn grid_cell(point: Vector2F) -> GridCell {
+    let scale = 1.0 / util::EPSILON as f64;
+    ((point.x() as f64 * scale).round() as i64, (point.y() as f64 * scale).round() as i64)
+}
+
+// A port of `computeCoverage()` in `shaders/fill.inc.glsl`, for a segment whose endpoints are
+// relative to the center of the pixel.
+fn fill_shader_coverage(from: Vector2F, to: Vector2F) -> f32 {
+    let mix = |x: f32, y: f32, a: f32| x * (1.0 - a) + y * a;
+    let (left, right) = if from.x() < to.x() { (from, to) } else { (to, from) };
+    let window = (util::clamp(from.x(), -0.5, 0.5), util::clamp(to.x(), -0.5, 0.5));
+    let offset = mix(window.0, window.1, 0.5) - left.x();
+    let t = offset / (right.x() - left.x());
+    let y = mix(left.y(), right.y(), t);
+    let d = (right.y() - left.y()) / (right.x() - left.x());
+    let dx = window.0 - window.1;
+    sample_area_lut((y + 8.0) / 16.0, f32::abs(d * dx) / 16.0) * dx
+}
+
+// Samples the first channel of the area lookup table at the normalized coordinates `(u, v)`,
+// with bilinear filtering and clamping to the edges.
+fn sample_area_lut(u: f32, v: f32) -> f32 {
+    let (u, v) = (u * AREA_LUT_SIZE as f32 - 0.5, v * AREA_LUT_SIZE as f32 - 0.5);
+    let (u0, v0) = (u.floor(), v.floor());
+    let (u_frac, v_frac) = (u - u0, v - v0);
+    let (u0, v0) = (u0 as i32, v0 as i32);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let row = |v: i32| lerp(area_lut_texel(u0, v), area_lut_texel(u0 + 1, v), u_frac);
+    lerp(row(v0), row(v0 + 1), v_frac)
+}
+
+// Returns texel `(u, v)` of the first channel of the area lookup table, normalized to [0, 1], as
+// `utils/area-lut` generates it. Out-of-range coordinates are clamped to the edges.
+fn area_lut_texel(u: i32, v: i32) -> f32 {
+    let max = AREA_LUT_SIZE as i32 - 1;
+    let (u, v) = (i32::min(i32::max(u, 0), max), i32::min(i32::max(v, 0), max));
+    if u == 0 {
+        return 1.0;
+    }
+    if u == max {
+        return 0.0;
+    }
+    let (y, dydx) = ((u - AREA_LUT_SIZE as i32 / 2) as f32 / 16.0, -(v as f32) / 16.0);
+    (area_lut_area(y, dydx) * 255.0).round() / 255.0
+}
+
+// The area of the unit pixel centered on the origin that lies on the positive-y side of the line
+// with the given y-intercept and slope, as `utils/area-lut` computes it, case for case.
+fn area_lut_area(y: f32, dydx: f32) -> f32 {
+    let solve_line_y = |p0: (f32, f32), p1: (f32, f32), y: f32| {
+        let m = (p1.1 - p0.1) / (p1.0 - p0.0);
+        (p0.0 - (p0.1 - y) / m, y)
+    };
+    let area_tri = |p0: (f32, f32), p1: (f32, f32)| 0.5 * (p1.0 - p0.0) * (p0.1 - p1.1);
+    let area_rect = |p0: (f32, f32), p1: (f32, f32)| (p1.0 - p0.0) * (p0.1 - p1.1);
+
+    let (x_left, x_right) = (-0.5, 0.5);
+    let (p0, p1) = ((x_left, dydx * x_left + y), (x_right, dydx * x_right + y));
+    let p2 = solve_line_y(p0, p1, -0.5);
+    let p3 = (p1.0, -0.5);
+    let p4 = solve_line_y(p0, p1, 0.5);
+    let p7 = (p1.0, 0.5);
+
+    if p0.1 > 0.5 {
+        if p1.1 < -0.5 {
+            area_tri(p0, p1) - area_tri(p2, p1) - area_rect(p0, p7) + area_tri(p0, p4)
+        } else if p1.1 < 0.5 {
+            area_tri(p0, p1) - area_rect(p0, p7) + area_tri(p0, p4)
+        } else {
+            0.0
+        }
+    } else if p0.1 > -0.5 {
+        if p1.1 < -0.5 {
+            area_tri(p0, p1) - area_tri(p2, p1) - area_rect(p0, p7)
+        } else {
+            area_tri(p0, p1) - area_rect(p0, p7)
+        }
+    } else {
+        area_rect(p0, p3) - area_rect(p0, p7)
+    }
+}
+
+// Distributes the signed height `d` of an edge crossing a pixel row from `x0` to `x1` into
+// per-cell deltas, using the exact area covered to the right of the edge in each cell. `add` is
+// called with each cell index and its delta.
+//
+// The part of the edge to the left of x = 0 covers the whole row to its right, so it's
+// accumulated as a vertical edge at x = 0 carrying its share of the height, and only the rest of
+// the edge is distributed.
+fn accumulate_span<F>(x0: f32, x1: f32, d: f32, mut add: F) where F: FnMut(usize, f32) {
+    let mut add = |index: i32, value: f32| add(index as usize, value);
+
+    let (min_x, max_x) = (f32::min(x0, x1), f32::max(x0, x1));
+    let left_d = if max_x <= 0.0 {
+        d
+    } else if min_x < 0.0 {
+        d * -min_x / (max_x - min_x)
+    } else {
+        0.0
+    };
+    let d = d - left_d;
+
+    let (x0, x1) = (f32::max(min_x, 0.0), f32::max(max_x, 0.0));
+    let x0_floor = x0.floor();
+    let x1_ceil = x1.ceil();
+    let (x0i, x1i) = (x0_floor as i32, x1_ceil as i32);
+
+    if x1i <= x0i + 1 {
+        let x_mid = 0.5 * (x0 + x1) - x0_floor;
+        add(x0i, left_d + d - d * x_mid);
+        add(x0i + 1, d * x_mid);
+        return;
+    }
+
+    let s = 1.0 / (x1 - x0);
+    let x0_frac = x0 - x0_floor;
+    let a0 = 0.5 * s * (1.0 - x0_frac) * (1.0 - x0_frac);
+    let x1_frac = x1 - x1_ceil + 1.0;
+    let am = 0.5 * s * x1_frac * x1_frac;
+    add(x0i, left_d + d * a0);
+    if x1i == x0i + 2 {
+        add(x0i + 1, d * (1.0 - a0 - am));
+    } else {
+        let a1 = s * (1.5 - x0_frac);
+        add(x0i + 1, d * (a1 - a0));
+        for xi in (x0i + 2)..(x1i - 1) {
+            add(xi, d * s);
+        }
+        let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+        add(x1i - 1, d * (1.0 - a2 - am));
+    }
+    add(x1i, d * am);
+}
+
+impl Add<Vector2F> for LineSegment2F {
+    type Output = LineSegment2F;
+    #[inline]
+    fn add(self, point: Vector2F) -> LineSegment2F {
+        LineSegment2F(self.0 + point.0.to_f32x4().xyxy())
+    }
+}
+
+impl Sub<Vector2F> for LineSegment2F {
+    type Output = LineSegment2F;
+    #[inline]
+    fn sub(self, point: Vector2F) -> LineSegment2F {
+        LineSegment2F(self.0 - point.0.to_f32x4().xyxy())
+    }
+}
+
+impl Mul<Vector2F> for LineSegment2F {
+    type Output = LineSegment2F;
+    #[inline]
+    fn mul(self, factors: Vector2F) -> LineSegment2F {
+        LineSegment2F(self.0 * factors.0.to_f32x4().xyxy())
+    }
+}
+
+impl Mul<f32> for LineSegment2F {
+    type Output = LineSegment2F;
+    #[inline]
+    fn mul(self, factor: f32) -> LineSegment2F {
+        LineSegment2F(self.0 * F32x4::splat(factor))
+    }
+}
+
+impl MulAssign<Vector2F> for LineSegment2F {
+    #[inline]
+    fn mul_assign(&mut self, factors: Vector2F) {
+        *self = *self * factors
+    }
+}
+
+// An endpoint of a segment, as processed by a sweep line for boolean path operations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepEvent {
+    pub point: Vector2F,
+    // The segment's other endpoint.
+    pub other_point: Vector2F,
+    // The polygon that the segment belongs to.
+    pub polygon_id: u8,
+    // Whether `point` is the segment's left endpoint under the sweep order.
+    pub is_left: bool,
+}
+
+impl SweepEvent {
+    #[inline]
+    pub fn segment(&self) -> LineSegment2F {
+        if self.is_left {
+            LineSegment2F::new(self.point, self.other_point)
+        } else {
+            LineSegment2F::new(self.other_point, self.point)
+        }
+    }
+}
+
+// Accumulates the coverage of a path over a grid of unit pixels with its origin at (0, 0), one
+// segment at a time.
+//
+// Each segment's signed trapezoidal areas are stored as per-row deltas, as in
+// `LineSegment2F::accumulate_into_scanline()`, rather than as coverage, so contributions of
+// adjacent segments to a shared pixel combine exactly. A closed contour split into any number of
+// segments therefore resolves to the same coverage as the contour itself, without seams.
+//
+// Segments are accumulated lazily, when the coverage is resolved, so each call to `resolve()`
+// accumulates every segment again, in time proportional to the number of segments. A segment
+// that runs the opposite way to a previously-added segment, with endpoints matching its endpoints
+// to within `util::EPSILON`, cancels it outright instead of being accumulated, so the boundary
+// shared by two abutting regions of opposite winding leaves no residue from rounding. Endpoints
+// match when they round to the same cell of a grid with `util::EPSILON` spacing, so endpoints
+// that are close but fall on either side of a cell boundary don't cancel.
+#[derive(Clone, Debug)]
+pub struct CoverageAccumulator {
+    size: Vector2I,
+    segments: Vec<Option<LineSegment2F>>,
+    open_edges: HashMap<(GridCell, GridCell), Vec<usize>>,
+}
+
+impl CoverageAccumulator {
+    #[inline]
+    pub fn new(size: Vector2I) -> CoverageAccumulator {
+        debug_assert!(size.x() >= 0 && size.y() >= 0);
+        CoverageAccumulator { size, segments: vec![], open_edges: HashMap::new() }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Vector2I {
+        self.size
+    }
+
+    pub fn add_segment(&mut self, segment: LineSegment2F) {
+        let (from, to) = (grid_cell(segment.from()), grid_cell(segment.to()));
+        if let Some(indices) = self.open_edges.get_mut(&(to, from)) {
+            if let Some(index) = indices.pop() {
+                self.segments[index] = None;
+                return;
+            }
+        }
+        self.open_edges.entry((from, to)).or_default().push(self.segments.len());
+        self.segments.push(Some(segment));
+    }
+
+    // Returns the coverage of each pixel in row-major order, under the nonzero fill rule.
+    pub fn resolve(&self) -> Vec<f32> {
+        let stride = self.size.x() as usize + 1;
+        let mut deltas = vec![0.0; stride * self.size.y() as usize];
+        for segment in self.segments.iter().flatten() {
+            let first_row = f32::max(segment.min_y().floor(), 0.0) as usize;
+            let last_row = f32::min(segment.max_y().ceil(), self.size.y() as f32) as usize;
+            for row in first_row..last_row {
+                let row_deltas = &mut deltas[row * stride..(row + 1) * stride];
+                segment.accumulate_into_scanline(row as f32, row_deltas, 0.0);
+            }
+        }
+
+        let mut coverage = Vec::with_capacity(self.size.x() as usize * self.size.y() as usize);
+        for row_deltas in deltas.chunks(stride) {
+            let mut sum = 0.0;
+            for &delta in &row_deltas[..stride - 1] {
+                sum += delta;
+                coverage.push(f32::min(f32::abs(sum), 1.0));
+            }
+        }
+        coverage
+    }
+}
+
+// Triangulates a y-monotone polygon incrementally, with the stack-based algorithm for monotone
+// polygons. Vertices are pushed in order of decreasing y, each tagged with the chain it lies on;
+// the topmost and bottommost vertices, which lie on both chains, may be tagged with either.
+// Triangles are emitted as soon as their diagonals are known to lie inside the polygon, using
+// `LineSegment2F::side_of_point()` to test the convexity of the chain on the stack.
+#[derive(Clone, Debug, Default)]
+pub struct MonotoneTriangulator {
+    stack: Vec<(Vector2F, bool)>,
+    triangles: Vec<[Vector2F; 3]>,
+}
+
+impl MonotoneTriangulator {
+    #[inline]
+    pub fn new() -> MonotoneTriangulator {
+        MonotoneTriangulator::default()
+    }
+
+    pub fn push_vertex(&mut self, point: Vector2F, is_left_chain: bool) {
+        let (top, top_is_left_chain) = match self.stack.last() {
+            None => {
+                self.stack.push((point, is_left_chain));
+                return;
+            }
+            Some(&top) => top,
+        };
+
+        if top_is_left_chain != is_left_chain {
+            // Every vertex on the stack can see the new one across the polygon.
+            for pair in self.stack.windows(2) {
+                self.triangles.push([pair[0].0, pair[1].0, point]);
+            }
+            self.stack.clear();
+            self.stack.push((top, top_is_left_chain));
+            self.stack.push((point, is_left_chain));
+            return;
+        }
+
+        let mut last = self.stack.pop().unwrap();
+        while let Some(&(next, _)) = self.stack.last() {
+            // The diagonal from `next` to the new vertex is inside if `last` bulges outward.
+            let side = LineSegment2F::new(next, point).side_of_point(last.0);
+            if (is_left_chain && side >= 0.0) || (!is_left_chain && side <= 0.0) {
+                break;
+            }
+            self.triangles.push([next, last.0, point]);
+            last = self.stack.pop().unwrap();
+        }
+        self.stack.push(last);
+        self.stack.push((point, is_left_chain));
+    }
+
+    // Returns the triangles of the polygon, treating the last vertex pushed as the bottommost.
+    pub fn take_triangles(mut self) -> Vec<[Vector2F; 3]> {
+        if let Some(&(bottom, _)) = self.stack.last() {
+            let remaining = self.stack.len() - 1;
+            for pair in self.stack[..remaining].windows(2) {
+                self.triangles.push([pair[0].0, pair[1].0, bottom]);
+            }
+        }
+        self.triangles
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct LineSegmentU4 {
+    pub from: u8,
+    pub to: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct LineSegmentU8 {
+    pub from_x: u8,
+    pub from_y: u8,
+    pub to_x: u8,
+    pub to_y: u8,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::line_segment::{self, CoverageAccumulator, LineSegment2F, MonotoneTriangulator};
+    use crate::rect::RectF;
+    use crate::transform2d::Matrix2x2F;
+    use crate::util;
+    use crate::vector::{Vector2F, vec2f, vec2i};
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
+    use std::f32::consts::PI;
+
+    fn running_sum(deltas: &[f32]) -> Vec<f32> {
+        deltas.iter().scan(0.0, |sum, delta| { *sum += delta; Some(*sum) }).collect()
+    }
+
+    // Returns the edges of the closed polygon with the given vertices.
+    fn polygon_segments(points: &[Vector2F]) -> Vec<LineSegment2F> {
+        (0..points.len()).map(|index| {
+            LineSegment2F::new(points[index], points[(index + 1) % points.len()])
+        }).collect()
+    }
+
+    #[test]
+    fn test_accumulate_into_scanline() {
+        let mut coverage = vec![0.0; 4];
+        LineSegment2F::new(vec2f(1.5, -1.0), vec2f(1.5, 2.0))
+            .accumulate_into_scanline(0.0, &mut coverage, 0.0);
+        let coverage = running_sum(&coverage);
+        assert!(util::approx_eq(coverage[0], 0.0));
+        assert!(util::approx_eq(coverage[1], 0.5));
+        assert!(util::approx_eq(coverage[2], 1.0));
+
+        // An upward diagonal crossing three pixels contributes its full negative winding.
+        let mut coverage = vec![0.0; 5];
+        LineSegment2F::new(vec2f(3.5, 1.0), vec2f(0.5, 0.0))
+            .accumulate_into_scanline(0.0, &mut coverage, 0.0);
+        let coverage = running_sum(&coverage);
+        assert!(util::approx_eq(coverage[0], -0.125 / 3.0));
+        assert!(util::approx_eq(coverage[4], -1.0));
+
+        // A polygon whose edges cross the left edge of the buffer partway through the row.
+        let quad = polygon_segments(&[vec2f(-2.0, 0.5), vec2f(5.0, 0.0), vec2f(6.0, 3.0),
+                                      vec2f(1.0, 3.5)]);
+        let expected = [
+            [0.678571, 0.75, 0.821429, 0.892857, 0.964286, 0.166667, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.0, 0.0],
+            [0.875, 1.0, 1.0, 1.0, 1.0, 0.833333, 0.0, 0.0],
+            [0.125, 0.45, 0.35, 0.25, 0.15, 0.05, 0.0, 0.0],
+        ];
+        for (row, expected) in expected.iter().enumerate() {
+            let mut coverage = vec![0.0; 9];
+            for segment in &quad {
+                segment.accumulate_into_scanline(row as f32, &mut coverage, 0.0);
+            }
+            for (&coverage, &expected) in running_sum(&coverage).iter().zip(expected.iter()) {
+                assert!(f32::abs(f32::abs(coverage) - expected) < 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_snap_preserving_slope() {
+        let segment = LineSegment2F::new(vec2f(0.9, 2.0), vec2f(1.2, 1.6));
+        assert!(segment.snap_to_grid(1.0).is_zero_length());
+        assert_eq!(segment.snap_preserving_slope(1.0),
+                   LineSegment2F::new(vec2f(1.0, 2.0), vec2f(1.0, 1.0)));
+
+        let segment = LineSegment2F::new(vec2f(0.2, 0.0), vec2f(2.7, 0.6));
+        assert_eq!(segment.snap_preserving_slope(1.0), segment.snap_to_grid(1.0));
+    }
+
+    #[test]
+    fn test_trapezoid_area() {
+        let segment = LineSegment2F::new(vec2f(1.0, 0.0), vec2f(3.0, 2.0));
+        assert!(util::approx_eq(segment.trapezoid_area(0.0), 4.0));
+        assert!(util::approx_eq(segment.reversed().trapezoid_area(0.0), -4.0));
+        assert!(util::approx_eq(segment.trapezoid_area(2.0), 0.0));
+        assert!(util::approx_eq(segment.trapezoid_area(4.0), -4.0));
+    }
+
+    #[test]
+    fn test_coverage_in_parallelogram() {
+        let diamond = [vec2f(1.0, 0.0), vec2f(2.0, 1.0), vec2f(1.0, 2.0), vec2f(0.0, 1.0)];
+        let segment = LineSegment2F::new(vec2f(1.0, 0.0), vec2f(1.0, 2.0));
+        assert!(util::approx_eq(segment.coverage_in_parallelogram(diamond), 0.5));
+
+        let segment = LineSegment2F::new(vec2f(0.0, 0.5), vec2f(2.0, 0.5));
+        assert!(util::approx_eq(segment.coverage_in_parallelogram(diamond), 0.875));
+        assert!(util::approx_eq(segment.reversed().coverage_in_parallelogram(diamond), 0.125));
+    }
+
+    #[test]
+    fn test_clip_to_nonzero_region() {
+        // Two overlapping squares wound the same way, as one self-overlapping path.
+        let mut path = vec![];
+        for &(min, max) in &[(0.0, 2.0), (1.0, 3.0)] {
+            let corners = [vec2f(min, min), vec2f(max, min), vec2f(max, max), vec2f(min, max)];
+            path.extend(polygon_segments(&corners));
+        }
+        assert_eq!(line_segment::batch_winding(&path, vec2f(1.5, 1.5)), 2);
+
+        let segment = LineSegment2F::new(vec2f(-1.0, 1.5), vec2f(4.0, 1.5));
+        assert_eq!(segment.clip_to_nonzero_region(&path),
+                   vec![LineSegment2F::new(vec2f(0.0, 1.5), vec2f(3.0, 1.5))]);
+    }
+
+    #[test]
+    fn test_exact_pixel_coverage() {
+        let pixel = RectF::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0));
+        let segment = LineSegment2F::new(vec2f(-1.0, 0.25), vec2f(2.0, 0.25));
+        assert!(util::approx_eq(segment.exact_pixel_coverage(pixel), 0.75));
+        assert!(util::approx_eq(segment.reversed().exact_pixel_coverage(pixel), -0.75));
+        let segment = LineSegment2F::new(vec2f(0.0, 1.0), vec2f(1.0, 0.0));
+        assert!(util::approx_eq(segment.exact_pixel_coverage(pixel), 0.5));
+        let segment = LineSegment2F::new(vec2f(0.5, -4.0), vec2f(0.75, 4.0));
+        assert!(util::approx_eq(segment.exact_pixel_coverage(pixel), 0.140625));
+    }
+
+    #[test]
+    fn test_exact_pixel_coverage_regimes_agree() {
+        // Both integrations must agree at the 45° crossover between them.
+        let pixel = RectF::new(vec2f(3.0, 5.0), vec2f(1.0, 1.0));
+        for step in 0..64 {
+            let offset = step as f32 / 16.0 - 2.0;
+            for &slope in &[0.999, 1.0, 1.001, -0.999, -1.0, -1.001] {
+                let from = vec2f(2.5, 5.5 + offset);
+                let segment = LineSegment2F::new(from, from + vec2f(2.0, 2.0 * slope));
+                let segment = segment.clip_to_x_strip(pixel.min_x(), pixel.max_x()).unwrap();
+                let (by_x, by_y) = (segment.area_below_by_x(pixel),
+                                    segment.area_below_by_y(pixel));
+                assert!(f32::abs(by_x - by_y) < 1e-5, "{:?}: {} vs. {}", segment, by_x, by_y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_gamma_coverage() {
+        // An edge through the middle of the second pixel covers half of it.
+        let mut coverage = vec![0.0; 3];
+        LineSegment2F::new(vec2f(1.5, 0.0), vec2f(1.5, 1.0))
+            .accumulate_into_scanline(0.0, &mut coverage, 0.0);
+        let coverage = running_sum(&coverage);
+        assert!(util::approx_eq(coverage[1], 0.5));
+        assert!(util::approx_eq(line_segment::resolve_gamma_coverage(coverage[1], 2.2),
+                                0.5f32.powf(1.0 / 2.2)));
+        assert!(util::approx_eq(line_segment::resolve_gamma_coverage(coverage[2], 2.2), 1.0));
+    }
+
+    #[test]
+    fn test_compare_at_x() {
+        let a = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(4.0, 4.0));
+        let b = LineSegment2F::new(vec2f(4.0, 0.0), vec2f(0.0, 4.0));
+        let c = LineSegment2F::new(vec2f(2.0, 4.0), vec2f(2.0, 2.0));
+        assert_eq!(a.compare_at_x(b, 1.0), Ordering::Less);
+        assert_eq!(a.compare_at_x(b, 3.0), Ordering::Greater);
+
+        // At the crossing, the shallower slope comes first, and vertical segments come last.
+        assert_eq!(b.compare_at_x(a, 2.0), Ordering::Less);
+        assert_eq!(a.compare_at_x(b, 2.0), Ordering::Greater);
+        assert_eq!(c.compare_at_x(b, 2.0), Ordering::Greater);
+        assert_eq!(a.compare_at_x(a.reversed(), 2.0), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_clip_to_x_strip() {
+        let segment = LineSegment2F::new(vec2f(4.0, 0.0), vec2f(0.0, 4.0));
+        let clipped = segment.clip_to_x_strip(1.0, 2.0).unwrap();
+        assert_eq!(clipped, LineSegment2F::new(vec2f(2.0, 2.0), vec2f(1.0, 3.0)));
+        assert!(segment.clip_to_x_strip(5.0, 6.0).is_none());
+
+        let vertical = LineSegment2F::new(vec2f(1.5, 0.0), vec2f(1.5, 4.0));
+        assert_eq!(vertical.clip_to_x_strip(1.0, 2.0), Some(vertical));
+        assert!(vertical.clip_to_x_strip(2.0, 3.0).is_none());
+    }
+
+    #[test]
+    fn test_clip_to_y_strip() {
+        let segment = LineSegment2F::new(vec2f(0.0, 4.0), vec2f(4.0, 0.0));
+        assert_eq!(segment.clip_to_y_strip(-1.0, 5.0), Some(segment));
+        assert_eq!(segment.clip_to_y_strip(1.0, 2.0),
+                   Some(LineSegment2F::new(vec2f(2.0, 2.0), vec2f(3.0, 1.0))));
+        assert!(segment.clip_to_y_strip(5.0, 6.0).is_none());
+        assert!(segment.clip_to_y_strip(-2.0, -1.0).is_none());
+
+        // A horizontal segment on an edge of the strip is kept whole.
+        let horizontal = LineSegment2F::new(vec2f(0.0, 2.0), vec2f(3.0, 2.0));
+        assert_eq!(horizontal.clip_to_y_strip(2.0, 3.0), Some(horizontal));
+        assert_eq!(horizontal.clip_to_y_strip(1.0, 2.0), Some(horizontal));
+        assert!(horizontal.clip_to_y_strip(2.5, 3.0).is_none());
+    }
+
+    #[test]
+    fn test_rasterize_distance_mask() {
+        let segment = LineSegment2F::new(vec2f(0.0, 0.5), vec2f(5.0, 0.5));
+        let bounds = RectF::new(vec2f(0.0, 0.0), vec2f(5.0, 3.0));
+        let mut mask = vec![0.0; 15];
+        mask[14] = 0.75;
+        segment.rasterize_distance_mask(bounds, (5, 3), 2.0, &mut mask);
+        assert!(mask[0..5].iter().all(|&alpha| util::approx_eq(alpha, 1.0)));
+        assert!(mask[5..10].iter().all(|&alpha| util::approx_eq(alpha, 0.5)));
+        assert!(mask[10..14].iter().all(|&alpha| util::approx_eq(alpha, 0.0)));
+        assert!(util::approx_eq(mask[14], 0.75));
+    }
+
+    #[test]
+    fn test_edge_deltas() {
+        // A closed rectangle, accumulated into a winding image.
+        let (a, b) = (vec2f(0.25, 0.25), vec2f(2.75, 0.25));
+        let (c, d) = (vec2f(2.75, 2.75), vec2f(0.25, 2.75));
+        let bounds = RectF::new(vec2f(0.0, 0.0), vec2f(4.0, 4.0));
+        let mut image = vec![0; 16];
+        for &(from, to) in &[(a, b), (b, c), (c, d), (d, a)] {
+            for (column, row, delta) in LineSegment2F::new(from, to).edge_deltas(bounds, (4, 4)) {
+                image[(row * 4 + column) as usize] += delta;
+            }
+        }
+        for row in image.chunks_mut(4) {
+            for column in 1..4 {
+                row[column] += row[column - 1];
+            }
+        }
+        assert_eq!(image, vec![-1, -1, -1, 0, -1, -1, -1, 0, -1, -1, -1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_parity_crossings() {
+        let bounds = RectF::new(vec2f(0.0, 0.0), vec2f(4.0, 4.0));
+        let parity = |points: &[Vector2F]| {
+            let mut image = vec![false; 16];
+            for segment in polygon_segments(points) {
+                for (column, row) in segment.parity_crossings(bounds, (4, 4)) {
+                    image[(row * 4 + column) as usize] ^= true;
+                }
+            }
+            for row in image.chunks_mut(4) {
+                for column in 1..4 {
+                    row[column] ^= row[column - 1];
+                }
+            }
+            image.into_iter().map(|inside| inside as u8).collect::<Vec<_>>()
+        };
+
+        // The left and right vertices lie on the center line of row 1. Each is crossed by only
+        // one of its two edges, so the shared vertex counts once.
+        let diamond = [vec2f(2.0, 0.0), vec2f(3.5, 1.5), vec2f(2.0, 3.0), vec2f(0.5, 1.5)];
+        assert_eq!(parity(&diamond), vec![0, 1, 0, 0, 1, 1, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+
+        // The top and bottom vertices lie on center lines, where the polygon only touches the
+        // row. Both edges or neither count there, leaving the parity unchanged.
+        let diamond = [vec2f(2.0, 0.5), vec2f(3.5, 2.0), vec2f(2.0, 3.5), vec2f(0.5, 2.0)];
+        assert_eq!(parity(&diamond), vec![0, 0, 0, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_arc_through_three_points() {
+        let (a, b, c) = (vec2f(1.0, 0.0), vec2f(0.0, 1.0), vec2f(-1.0, 0.0));
+        let arc = line_segment::arc_through_three_points(a, b, c, 0.01).unwrap();
+        assert!(arc.len() > 2);
+        assert_eq!(arc[0].from(), a);
+        assert_eq!(arc[arc.len() - 1].to(), c);
+        assert!(arc.iter().all(|segment| {
+            util::approx_eq(segment.to().length(), 1.0) && segment.midpoint().y() > 0.0
+        }));
+
+        let collinear = vec2f(2.0, 0.0);
+        assert!(line_segment::arc_through_three_points(a, collinear, c, 0.01).is_none());
+
+        // A radius large enough that `1 - tolerance / radius` rounds to 1 in single precision.
+        let (a, b, c) = (vec2f(0.0, 0.0), vec2f(5000.0, 100.0), vec2f(10000.0, 0.0));
+        let arc = line_segment::arc_through_three_points(a, b, c, 0.001).unwrap();
+        assert!(arc.len() > 1 && arc.len() < 1000);
+        assert_eq!(arc[0].from(), a);
+        assert_eq!(arc[arc.len() - 1].to(), c);
+        assert!(arc.windows(2).all(|pair| pair[0].to() == pair[1].from()));
+        assert!(arc.iter().all(|segment| segment.midpoint().y() > -0.01));
+        let segments = line_segment::round_corners(&[a, b, c], 1.0e6, 0.001, false);
+        assert!(!segments.is_empty() && segments.len() <= line_segment::MAX_ARC_SEGMENTS + 2);
+    }
+
+    #[test]
+    fn test_round_corners() {
+        let square = [vec2f(0.0, 0.0), vec2f(10.0, 0.0), vec2f(10.0, 10.0), vec2f(0.0, 10.0)];
+        let segments = line_segment::round_corners(&square, 2.0, 0.01, true);
+        assert!(segments.windows(2).all(|pair| pair[0].to() == pair[1].from()));
+        assert_eq!(segments[0], LineSegment2F::new(vec2f(2.0, 0.0), vec2f(8.0, 0.0)));
+        assert_eq!(segments[segments.len() - 1].to(), segments[0].from());
+
+        // The radius is clamped so that fillets on a short edge meet in its middle.
+        let segments = line_segment::round_corners(&square[0..3], 20.0, 0.01, false);
+        assert_eq!(segments[0], LineSegment2F::new(vec2f(0.0, 0.0), vec2f(5.0, 0.0)));
+        assert_eq!(segments[segments.len() - 1],
+                   LineSegment2F::new(vec2f(10.0, 5.0), vec2f(10.0, 10.0)));
+    }
+
+    #[test]
+    fn test_should_flip() {
+        // A thin quadrilateral, whose short diagonal is the Delaunay edge.
+        let shared = LineSegment2F::new(vec2f(-5.0, 0.0), vec2f(5.0, 0.0));
+        let (p, q) = (vec2f(0.0, 1.0), vec2f(0.0, -1.0));
+        assert!(line_segment::should_flip(shared, p, q, false));
+        assert!(line_segment::should_flip(shared, q, p, false));
+        assert!(line_segment::should_flip(shared.reversed(), p, q, false));
+        assert!(!line_segment::should_flip(shared, p, q, true));
+
+        let shared = LineSegment2F::new(vec2f(0.0, 1.0), vec2f(0.0, -1.0));
+        let (p, q) = (vec2f(-5.0, 0.0), vec2f(5.0, 0.0));
+        assert!(!line_segment::should_flip(shared, p, q, false));
+    }
+
+    #[test]
+    fn test_point_in_triangle() {
+        let (a, b, c) = (vec2f(0.0, 0.0), vec2f(4.0, 0.0), vec2f(0.0, 4.0));
+        assert!(line_segment::point_in_triangle(vec2f(1.0, 1.0), a, b, c));
+        assert!(line_segment::point_in_triangle(vec2f(1.0, 1.0), a, c, b));
+        assert!(line_segment::point_in_triangle(vec2f(2.0, 2.0), a, b, c));
+        assert!(line_segment::point_in_triangle(a, a, b, c));
+        assert!(!line_segment::point_in_triangle(vec2f(2.1, 2.1), a, b, c));
+        assert!(!line_segment::point_in_triangle(vec2f(-1.0, 1.0), a, b, c));
+    }
+
+    #[test]
+    fn test_soa_round_trip() {
+        let segments: Vec<_> = (0..6).map(|index| {
+            let index = index as f32;
+            LineSegment2F::new(vec2f(index, index + 0.25), vec2f(index + 0.5, index + 0.75))
+        }).collect();
+        let (mut from_x, mut from_y, mut to_x, mut to_y) = (vec![], vec![], vec![], vec![]);
+        line_segment::to_soa(&segments, &mut from_x, &mut from_y, &mut to_x, &mut to_y);
+        assert_eq!(from_x, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(from_y, vec![0.25, 1.25, 2.25, 3.25, 4.25, 5.25]);
+        assert_eq!(to_x, vec![0.5, 1.5, 2.5, 3.5, 4.5, 5.5]);
+        assert_eq!(to_y, vec![0.75, 1.75, 2.75, 3.75, 4.75, 5.75]);
+        assert_eq!(line_segment::from_soa(&from_x, &from_y, &to_x, &to_y), segments);
+    }
+
+    #[test]
+    fn test_offset_with_loop_removal() {
+        // Two squares joined by a narrow neck with slanted walls, which an inward offset pinches.
+        let points = [
+            vec2f(0.0, 0.0), vec2f(10.0, 0.0), vec2f(11.0, 4.0), vec2f(13.0, 4.0),
+            vec2f(14.0, 0.0), vec2f(24.0, 0.0), vec2f(24.0, 10.0), vec2f(14.0, 10.0),
+            vec2f(13.0, 6.0), vec2f(11.0, 6.0), vec2f(10.0, 10.0), vec2f(0.0, 10.0),
+        ];
+        let segments = polygon_segments(&points);
+
+        let contours = line_segment::offset_with_loop_removal(&segments, 1.5);
+        assert_eq!(contours.len(), 2);
+        let areas: Vec<_> = contours.iter().map(|contour| {
+            assert!(line_segment::self_intersections(contour).is_empty());
+            let points: Vec<_> = contour.iter().map(|segment| segment.from()).collect();
+            line_segment::polygon_area(&points)
+        }).collect();
+        assert!(areas[0] > 0.0 && util::approx_eq(areas[0], areas[1]));
+
+        // A gentler offset keeps the neck.
+        let contours = line_segment::offset_with_loop_removal(&segments, 0.5);
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours[0].len(), 12);
+    }
+
+    #[test]
+    fn test_miter_clip_join() {
+        let incoming = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+        let outgoing = LineSegment2F::new(vec2f(10.0, 0.0), vec2f(10.0, 10.0));
+        let join = line_segment::miter_clip_join(incoming, outgoing, 1.0, 4.0);
+        let expected = [vec2f(10.0, 0.0), vec2f(10.0, -1.0), vec2f(11.0, -1.0), vec2f(11.0, 0.0)];
+        assert_eq!(join.len(), expected.len());
+        for (&point, &expected) in join.iter().zip(expected.iter()) {
+            assert!((point - expected).length() < util::EPSILON);
+        }
+
+        // A right angle's miter extends √2 half widths, so a limit of 1 clips it.
+        let join = line_segment::miter_clip_join(incoming, outgoing, 1.0, 1.0);
+        assert_eq!(join.len(), 5);
+        for &point in &join[2..4] {
+            assert!(util::approx_eq((point - vec2f(10.0, 0.0)).dot(vec2f(1.0, -1.0).normalize()),
+                                    1.0));
+        }
+    }
+
+    #[test]
+    fn test_accumulate_coverage_compensated() {
+        // Many tiny copies of one vertical edge in the middle of a 16-pixel tile.
+        let segment = LineSegment2F::new(vec2f(8.0, 0.0), vec2f(8.0, 0.001));
+        let (mut coverage, mut compensation) = (vec![0.0; 2], vec![0.0; 2]);
+        for _ in 0..1_000_000 {
+            segment.accumulate_coverage_compensated(&mut coverage, &mut compensation, 16.0);
+        }
+        let expected = 1_000_000.0 * (0.001f32 / 16.0 * 0.5) as f64;
+        assert!(f64::abs(coverage[0] as f64 - expected) < 1e-5 * expected);
+        assert!(f64::abs(coverage[1] as f64 - expected) < 1e-5 * expected);
+    }
+
+    #[test]
+    fn test_recommended_samples() {
+        let horizontal = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+        let diagonal = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0));
+        let steep = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(2.0, 10.0));
+        assert_eq!(horizontal.recommended_samples(0.01), 1);
+        assert!(diagonal.recommended_samples(0.01) > steep.recommended_samples(0.01));
+        assert!((diagonal * 2.0).recommended_samples(0.01) > diagonal.recommended_samples(0.01));
+        assert_eq!(LineSegment2F::new(vec2f(1.0, 1.0), vec2f(1.0, 1.0)).recommended_samples(0.01),
+                   1);
+    }
+
+    #[test]
+    fn test_fit_quadratic() {
+        let (start, ctrl, end) = (vec2f(0.0, 0.0), vec2f(5.0, 10.0), vec2f(10.0, 0.0));
+        let points: Vec<_> = (0..=32).map(|step| {
+            let t = step as f32 / 32.0;
+            start.lerp(ctrl, t).lerp(ctrl.lerp(end, t), t)
+        }).collect();
+        let (fit_start, fit_ctrl, fit_end) = line_segment::fit_quadratic(&points, 0.1).unwrap();
+        assert_eq!((fit_start, fit_end), (start, end));
+        assert!((fit_ctrl - ctrl).length() < 0.5);
+
+        // A zigzag can't be approximated by one quadratic.
+        let zigzag = [vec2f(0.0, 0.0), vec2f(2.0, 4.0), vec2f(4.0, 0.0), vec2f(6.0, 4.0)];
+        assert!(line_segment::fit_quadratic(&zigzag, 0.1).is_none());
+    }
+
+    #[test]
+    fn test_coverage_in_ellipse() {
+        // The inside of this segment is y > -0.05.
+        let segment = LineSegment2F::new(vec2f(-1.0, -0.05), vec2f(1.0, -0.05));
+        let flat = Matrix2x2F::from_scale(vec2f(1.0, 0.1));
+        let expected = (2.0 * PI / 3.0 + f32::sqrt(3.0) / 4.0) / PI;
+        assert!(util::approx_eq(segment.coverage_in_ellipse(Vector2F::zero(), flat), expected));
+        assert_eq!(segment.coverage_in_ellipse(vec2f(0.0, 1.0), flat), 1.0);
+        assert_eq!(segment.coverage_in_ellipse(vec2f(0.0, -1.0), flat), 0.0);
+        assert!(util::approx_eq(segment.coverage_in_ellipse(vec2f(0.0, -0.05), flat), 0.5));
+    }
+
+    #[test]
+    fn test_conservative_pixels() {
+        let bounds = RectF::new(vec2f(0.0, 0.0), vec2f(16.0, 8.0));
+        let segment = LineSegment2F::new(vec2f(1.3, 0.7), vec2f(14.2, 6.1));
+        let pixels = segment.conservative_pixels(bounds, (8, 8));
+
+        // Every pixel within half a pixel of a point on the segment must be included.
+        for step in 0..=1000 {
+            let point = segment.sample(step as f32 / 1000.0) * vec2f(0.5, 1.0);
+            for &(dx, dy) in &[(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)] {
+                let corner = point + vec2f(dx, dy);
+                let (column, row) = (corner.x().floor(), corner.y().floor());
+                if (0.0..8.0).contains(&column) && (0.0..8.0).contains(&row) {
+                    assert!(pixels.contains(&(column as u32, row as u32)));
+                }
+            }
+        }
+        assert!(!pixels.contains(&(7, 0)));
+        assert!(!pixels.contains(&(0, 7)));
+    }
+
+    #[test]
+    fn test_add_area_prefix() {
+        // A quadrilateral with one vertex inside the row and one edge crossing its top.
+        let points = [vec2f(0.5, -1.0), vec2f(6.2, 0.4), vec2f(3.7, 2.0), vec2f(1.1, 0.6)];
+        let (mut areas, mut backdrops, mut expected) = (vec![0.0; 8], vec![0; 8], vec![0.0; 8]);
+        for segment in polygon_segments(&points) {
+            segment.add_area_prefix(&mut areas, &mut backdrops, (0.0, 1.0));
+            segment.accumulate_into_scanline(0.0, &mut expected, 0.0);
+        }
+
+        let expected = running_sum(&expected);
+        let mut backdrop = 0;
+        for column in 0..8 {
+            backdrop += backdrops[column];
+            assert!(f32::abs(backdrop as f32 + areas[column] - expected[column]) < 0.0001);
+        }
+
+        // A triangle whose hypotenuse, x = 3 - 4y, crosses x = 0 within the row. Each pixel's
+        // area inside it is the mean of the hypotenuse's heights at the pixel's left and right.
+        let points = [vec2f(-1.0, 0.0), vec2f(3.0, 0.0), vec2f(-1.0, 1.0)];
+        let (mut areas, mut backdrops) = (vec![0.0; 5], vec![0; 5]);
+        for segment in polygon_segments(&points) {
+            segment.add_area_prefix(&mut areas, &mut backdrops, (0.0, 1.0));
+        }
+        let mut backdrop = 0;
+        for (column, &expected) in [0.625, 0.375, 0.125, 0.0, 0.0].iter().enumerate() {
+            backdrop += backdrops[column];
+            assert!(f32::abs(f32::abs(backdrop as f32 + areas[column]) - expected) < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_capsule_boundary_intersections() {
+        let axis = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+
+        // Across both flat sides.
+        let segment = LineSegment2F::new(vec2f(5.0, -5.0), vec2f(5.0, 5.0));
+        let points = segment.capsule_boundary_intersections(axis, 2.0);
+        assert_eq!(&points[..], &[vec2f(5.0, -2.0), vec2f(5.0, 2.0)]);
+
+        // Along the axis, through both caps.
+        let segment = LineSegment2F::new(vec2f(15.0, 0.0), vec2f(-5.0, 0.0));
+        let points = segment.capsule_boundary_intersections(axis, 2.0);
+        assert_eq!(&points[..], &[vec2f(12.0, 0.0), vec2f(-2.0, 0.0)]);
+
+        // From the inside, out through a cap.
+        let segment = LineSegment2F::new(vec2f(10.0, 0.0), vec2f(20.0, 10.0));
+        let points = segment.capsule_boundary_intersections(axis, 2.0);
+        assert_eq!(points.len(), 1);
+        assert!(util::approx_eq((points[0] - vec2f(10.0, 0.0)).length(), 2.0));
+
+        // Past the capsule.
+        let segment = LineSegment2F::new(vec2f(0.0, 5.0), vec2f(10.0, 5.0));
+        assert!(segment.capsule_boundary_intersections(axis, 2.0).is_empty());
+    }
+
+    #[test]
+    fn test_snap_angle() {
+        let segment = LineSegment2F::new(vec2f(1.0, 1.0), vec2f(4.0, 1.5));
+        let snapped = segment.snap_angle(8);
+        assert_eq!(snapped.from(), segment.from());
+        assert!((snapped.to() - vec2f(1.0 + segment.length(), 1.0)).length() < util::EPSILON);
+
+        let snapped = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(-2.0, -2.2)).snap_angle(8);
+        assert!(util::approx_eq(snapped.to_x(), snapped.to_y()));
+        assert!(util::approx_eq(snapped.length(), vec2f(2.0, 2.2).length()));
+    }
+
+    #[test]
+    fn test_visibility_blocking_angles() {
+        let source = vec2f(0.0, 0.0);
+        let segment = LineSegment2F::new(vec2f(1.0, 1.0), vec2f(1.0, -1.0));
+        assert!(util::approx_eq(segment.subtended_angle(source), -0.5 * PI));
+        let (start, end) = segment.visibility_blocking_angles(source).unwrap();
+        assert!(util::approx_eq(start, -0.25 * PI));
+        assert!(util::approx_eq(end, 0.25 * PI));
+
+        // Straddling the negative x axis.
+        let segment = LineSegment2F::new(vec2f(-1.0, 1.0), vec2f(-1.0, -1.0));
+        let (start, end) = segment.visibility_blocking_angles(source).unwrap();
+        assert!(util::approx_eq(start, 0.75 * PI));
+        assert!(util::approx_eq(end, 1.25 * PI));
+
+        let edge_on = LineSegment2F::new(vec2f(1.0, 1.0), vec2f(2.0, 2.0));
+        assert!(edge_on.visibility_blocking_angles(source).is_none());
+    }
+
+    #[test]
+    fn test_bevel_join_points() {
+        let incoming = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+        let outgoing = LineSegment2F::new(vec2f(10.0, 0.0), vec2f(10.0, 10.0));
+        let bevel = line_segment::bevel_join_points(incoming, outgoing, 1.0);
+        let miter = line_segment::miter_clip_join(incoming, outgoing, 1.0, 4.0);
+        assert_eq!(bevel, [miter[0], miter[1], miter[3]]);
+    }
+
+    #[test]
+    fn test_clip_to_filled_shape() {
+        // A square with a square hole, wound the other way.
+        let contour = |min: f32, max: f32, reverse: bool| {
+            let mut corners = [vec2f(min, min), vec2f(max, min), vec2f(max, max), vec2f(min, max)];
+            if reverse {
+                corners.reverse();
+            }
+            polygon_segments(&corners)
+        };
+        let contours = [contour(0.0, 4.0, false), contour(1.0, 3.0, true)];
+
+        let segment = LineSegment2F::new(vec2f(-1.0, 2.0), vec2f(5.0, 2.0));
+        assert_eq!(segment.clip_to_filled_shape(&contours),
+                   vec![LineSegment2F::new(vec2f(0.0, 2.0), vec2f(1.0, 2.0)),
+                        LineSegment2F::new(vec2f(3.0, 2.0), vec2f(4.0, 2.0))]);
+    }
+
+    #[test]
+    fn test_coverage_accumulator() {
+        let points = [vec2f(0.5, 0.3), vec2f(6.2, 1.4), vec2f(3.7, 5.9), vec2f(1.1, 3.6)];
+        let (mut whole, mut split) = (CoverageAccumulator::new(vec2i(8, 8)),
+                                      CoverageAccumulator::new(vec2i(8, 8)));
+        for segment in polygon_segments(&points) {
+            whole.add_segment(segment);
+
+            // Split the edge into many pieces, with endpoints falling inside pixels.
+            for step in 0..7 {
+                split.add_segment(LineSegment2F::new(segment.sample(step as f32 / 7.0),
+                                                     segment.sample((step + 1) as f32 / 7.0)));
+            }
+        }
+
+        let (whole, split) = (whole.resolve(), split.resolve());
+        let area = whole.iter().sum::<f32>();
+        assert!(f32::abs(area - f32::abs(super::polygon_area(&points))) < 0.001);
+        for (&whole, &split) in whole.iter().zip(split.iter()) {
+            assert!(f32::abs(whole - split) < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_f16_round_trip() {
+        for step in 0..=1600 {
+            let value = step as f32 * 0.01;
+            let segment = LineSegment2F::new(vec2f(value, 16.0 - value), vec2f(-value, 0.5));
+            let round_trip = LineSegment2F::from_f16(segment.to_f16());
+            assert!(f32::abs(round_trip.from_x() - segment.from_x()) <= 1.0 / 128.0);
+            assert!(f32::abs(round_trip.from_y() - segment.from_y()) <= 1.0 / 128.0);
+            assert!(f32::abs(round_trip.to_x() - segment.to_x()) <= 1.0 / 128.0);
+            assert_eq!(round_trip.to_y(), 0.5);
+        }
+
+        let segment = LineSegment2F::new(vec2f(1.0, -2.0), vec2f(65504.0, 0.0));
+        assert_eq!(segment.to_f16(), [0x3c00, 0xc000, 0x7bff, 0x0000]);
+
+        let extremes = LineSegment2F::new(vec2f(1.0e6, -1.0e6), vec2f(1.0e-30, 1.0e-6));
+        let round_trip = LineSegment2F::from_f16(extremes.to_f16());
+        assert_eq!(round_trip.from(), vec2f(65504.0, -65504.0));
+        assert_eq!(round_trip.to_x(), 0.0);
+        assert!(f32::abs(round_trip.to_y() - 1.0e-6) < 1.0 / (1 << 25) as f32);
+    }
+
+    #[test]
+    fn test_coverage_with_jitter() {
+        let pixel = RectF::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0));
+        let segment = LineSegment2F::new(vec2f(-0.5, 0.2), vec2f(1.5, 0.9));
+        assert_eq!(segment.coverage_with_jitter(pixel, Vector2F::zero()),
+                   segment.exact_pixel_coverage(pixel));
+        let shifted = RectF::new(vec2f(0.25, 0.1), vec2f(1.0, 1.0));
+        assert!(util::approx_eq(segment.coverage_with_jitter(pixel, vec2f(0.25, 0.1)),
+                                segment.exact_pixel_coverage(shifted)));
+    }
+
+    #[test]
+    fn test_bvh_primitive() {
+        let segment = LineSegment2F::new(vec2f(3.0, -1.0), vec2f(-2.0, 5.0));
+        let (bounds, centroid) = segment.bvh_primitive();
+        assert_eq!(bounds, RectF::from_points(vec2f(-2.0, -1.0), vec2f(3.0, 5.0)));
+        assert_eq!(centroid, vec2f(0.5, 2.0));
+        assert_eq!(segment.reversed().bvh_primitive(), (bounds, centroid));
+    }
+
+    #[test]
+    fn test_split_into_tiles() {
+        let segment = LineSegment2F::new(vec2f(2.0, 30.0), vec2f(40.0, 4.0));
+        let pieces = segment.split_into_tiles(16.0);
+        let tiles: Vec<_> = pieces.iter().map(|&(x, y, _)| (x, y)).collect();
+        assert_eq!(tiles, vec![(0, 1), (1, 1), (1, 0), (2, 0)]);
+        assert_eq!(pieces[0].2.from(), segment.from());
+        assert_eq!(pieces[3].2.to(), segment.to());
+        for pair in pieces.windows(2) {
+            assert!((pair[0].2.to() - pair[1].2.from()).length() < util::EPSILON);
+        }
+
+        // Through a tile corner.
+        let pieces = LineSegment2F::new(vec2f(8.0, 8.0), vec2f(24.0, 24.0)).split_into_tiles(16.0);
+        let tiles: Vec<_> = pieces.iter().map(|&(x, y, _)| (x, y)).collect();
+        assert_eq!(tiles, vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_exact_polygon_area() {
+        let square = [vec2f(0.0, 0.0), vec2f(3.0, 0.0), vec2f(3.0, 3.0), vec2f(0.0, 3.0)];
+        assert_eq!(line_segment::exact_polygon_area(&square, 256.0), 9.0);
+        let reversed: Vec<_> = square.iter().rev().cloned().collect();
+        assert_eq!(line_segment::exact_polygon_area(&reversed, 256.0), -9.0);
+
+        // A large polygon far from the origin, whose area is independent of the starting vertex.
+        let points: Vec<_> = (0..1000).map(|index| {
+            let angle = index as f32 * 2.0 * PI / 1000.0;
+            vec2f(100000.0 + 5000.0 * angle.cos(), -70000.0 + 3000.0 * angle.sin())
+        }).collect();
+        let area = line_segment::exact_polygon_area(&points, 16.0);
+        let mut rotated = points[500..].to_vec();
+        rotated.extend_from_slice(&points[..500]);
+        assert_eq!(line_segment::exact_polygon_area(&rotated, 16.0), area);
+        assert!(f64::abs(area - std::f64::consts::PI * 5000.0 * 3000.0) < 1.0e-3 * area);
+    }
+
+    #[test]
+    fn test_ray_winding_dir() {
+        let corners = [vec2f(0.0, 0.0), vec2f(4.0, 0.0), vec2f(4.0, 4.0), vec2f(0.0, 4.0)];
+        let path = polygon_segments(&corners);
+        let winding = |point: Vector2F, ray_dir: Vector2F| {
+            path.iter().map(|segment| segment.ray_winding_dir(point, ray_dir)).sum::<i32>()
+        };
+
+        let point = vec2f(1.0, 3.0);
+        let expected = line_segment::batch_winding(&path, point);
+        assert_eq!(winding(point, vec2f(1.0, 0.0)), expected);
+        assert_eq!(winding(point, vec2f(-0.3, 1.0)), expected);
+        assert_eq!(winding(point, vec2f(1.0, -1.0)), expected);
+        assert_eq!(winding(vec2f(5.0, 3.0), vec2f(-1.0, 0.2)), 0);
+    }
+
+    #[test]
+    fn test_tent_filter_coverage() {
+        // The inside of this segment is y > 0.
+        let shallow = LineSegment2F::new(vec2f(-1.0, 0.0), vec2f(1.0, 0.0));
+        assert!(util::approx_eq(shallow.tent_filter_coverage(vec2f(0.0, 0.5), 1.0), 0.875));
+        assert!(util::approx_eq(shallow.tent_filter_coverage(vec2f(3.0, 0.0), 1.0), 0.5));
+        assert_eq!(shallow.tent_filter_coverage(vec2f(0.0, -2.0), 1.0), 0.0);
+
+        // The inside of this segment is x < 0.
+        let steep = LineSegment2F::new(vec2f(0.0, -1.0), vec2f(0.0, 1.0));
+        assert!(util::approx_eq(steep.tent_filter_coverage(vec2f(0.5, 0.0), 1.0), 0.125));
+
+        // A diagonal through the center splits the filter evenly, and coverage rises smoothly as
+        // the filter moves inside.
+        let diagonal = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0));
+        assert!(util::approx_eq(diagonal.tent_filter_coverage(vec2f(0.0, 0.0), 1.0), 0.5));
+        let mut last_coverage = 0.0;
+        for step in 0..=20 {
+            let offset = step as f32 * 0.1 - 1.0;
+            let coverage = diagonal.tent_filter_coverage(vec2f(-offset, offset), 1.0);
+            assert!(coverage >= last_coverage);
+            last_coverage = coverage;
+        }
+        assert!(util::approx_eq(last_coverage, 1.0));
+    }
+
+    #[test]
+    fn test_quantize_coverage_dithered() {
+        let bayer = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+        let coverage = 100.25 / 255.0;
+        let mut sum = 0;
+        for y in 0..4 {
+            for x in 0..4 {
+                let value = line_segment::quantize_coverage_dithered(coverage, (x, y), &bayer);
+                assert!(value == 100 || value == 101);
+                assert_eq!(line_segment::quantize_coverage_dithered(coverage, (x + 4, y), &bayer),
+                           value);
+                sum += value as u32;
+            }
+        }
+        assert_eq!(sum, 100 * 16 + 4);
+        assert_eq!(line_segment::quantize_coverage_dithered(1.0, (3, 3), &bayer), 255);
+        assert_eq!(line_segment::quantize_coverage_dithered(0.0, (3, 3), &bayer), 0);
+
+        // The first entry's threshold is 1/32, so fractional parts below 31/32 round down.
+        assert_eq!(line_segment::quantize_coverage_dithered(100.96 / 255.0, (0, 0), &bayer), 100);
+        assert_eq!(line_segment::quantize_coverage_dithered(100.97 / 255.0, (0, 0), &bayer), 101);
+
+        // Without a matrix, values round to the nearest level.
+        assert_eq!(line_segment::quantize_coverage_dithered(100.49 / 255.0, (1, 2), &[]), 100);
+        assert_eq!(line_segment::quantize_coverage_dithered(100.51 / 255.0, (1, 2), &[]), 101);
+        assert_eq!(line_segment::quantize_coverage_dithered(2.0, (1, 2), &[]), 255);
+    }
+
+    #[test]
+    fn test_tangent_circles() {
+        let incoming = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+        let outgoing = LineSegment2F::new(vec2f(10.0, 0.0), vec2f(10.0, 10.0));
+        let centers = line_segment::tangent_circles(incoming, outgoing, 2.0);
+        assert_eq!(centers.len(), 2);
+        assert!((centers[0] - vec2f(8.0, 2.0)).length() < util::EPSILON);
+        assert!((centers[1] - vec2f(12.0, -2.0)).length() < util::EPSILON);
+
+        let parallel = LineSegment2F::new(vec2f(0.0, 5.0), vec2f(10.0, 5.0));
+        assert!(line_segment::tangent_circles(incoming, parallel, 2.0).is_empty());
+    }
+
+    #[test]
+    fn test_compactness() {
+        let square = polygon_segments(&[vec2f(0.0, 0.0), vec2f(2.0, 0.0), vec2f(2.0, 2.0),
+                                        vec2f(0.0, 2.0)]);
+        assert!(util::approx_eq(line_segment::signed_area(&square), 4.0));
+        assert!(util::approx_eq(line_segment::total_length(&square), 8.0));
+        assert!(util::approx_eq(line_segment::compactness(&square), 0.25 * PI));
+
+        let circle: Vec<_> = (0..256).map(|index| {
+            let angle = index as f32 * 2.0 * PI / 256.0;
+            vec2f(angle.cos(), angle.sin()) * 3.0
+        }).collect();
+        assert!(util::approx_eq(line_segment::compactness(&polygon_segments(&circle)), 1.0));
+        assert_eq!(line_segment::compactness(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_clip_to_viewport() {
+        let viewport = RectF::new(vec2f(0.0, 0.0), vec2f(100.0, 50.0));
+        let segment = LineSegment2F::new(vec2f(-20.0, 25.0), vec2f(50.0, -45.0));
+        let clipped = segment.clip_to_viewport(viewport, 10.0).unwrap();
+        assert!((clipped.from() - vec2f(-10.0, 15.0)).length() < util::EPSILON);
+        assert!((clipped.to() - vec2f(15.0, -10.0)).length() < util::EPSILON);
+
+        let outside = LineSegment2F::new(vec2f(-20.0, 25.0), vec2f(-12.0, 60.0));
+        assert!(outside.clip_to_viewport(viewport, 10.0).is_none());
+        assert!(outside.clip_to_viewport(viewport, 15.0).is_some());
+    }
+
+    #[test]
+    fn test_coverage_accumulator_cancels_shared_edges() {
+        let (a, b, c, d) = (vec2f(0.5, 0.5), vec2f(3.3, 0.5), vec2f(4.6, 6.5), vec2f(0.5, 6.5));
+        let (e, f) = (vec2f(7.5, 0.5), vec2f(7.5, 6.5));
+        let mut abutting = CoverageAccumulator::new(vec2i(8, 8));
+        for &(from, to) in &[(a, b), (b, c), (c, d), (d, a), (b, e), (e, f), (f, c), (c, b)] {
+            abutting.add_segment(LineSegment2F::new(from, to));
+        }
+        let mut merged = CoverageAccumulator::new(vec2i(8, 8));
+        for &(from, to) in &[(a, b), (c, d), (d, a), (b, e), (e, f), (f, c)] {
+            merged.add_segment(LineSegment2F::new(from, to));
+        }
+
+        let coverage = abutting.resolve();
+        assert_eq!(coverage, merged.resolve());
+        for y in 1..6 {
+            for x in 1..7 {
+                assert_eq!(coverage[y * 8 + x], 1.0);
+            }
+        }
+        assert_eq!(coverage[3], 0.5);
+
+        // Endpoints far from the origin that differ by more than `util::EPSILON` don't cancel.
+        let mut distant = CoverageAccumulator::new(vec2i(4, 4));
+        let (near, far) = (vec2f(0.5, 0.5), vec2f(5.0e6, 1.0));
+        distant.add_segment(LineSegment2F::new(near, far));
+        distant.add_segment(LineSegment2F::new(far + vec2f(4.0, 0.0), near));
+        assert_eq!(distant.segments.iter().flatten().count(), 2);
+        distant.add_segment(LineSegment2F::new(far, near));
+        assert_eq!(distant.segments.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn test_area_centroid() {
+        let square = |origin: Vector2F, size: f32, counterclockwise: bool| {
+            let mut points = [origin,
+                              origin + vec2f(size, 0.0),
+                              origin + vec2f(size, size),
+                              origin + vec2f(0.0, size)];
+            if counterclockwise {
+                points.reverse();
+            }
+            polygon_segments(&points)
+        };
+
+        let outline = square(vec2f(0.0, 0.0), 4.0, false);
+        let centroid = line_segment::area_centroid(&outline).unwrap();
+        assert!((centroid - vec2f(2.0, 2.0)).length() < 0.0001);
+
+        // A 2×2 hole in the lower right corner moves the centroid up and to the left.
+        let mut shape = outline;
+        shape.extend(square(vec2f(2.0, 2.0), 2.0, true));
+        let centroid = line_segment::area_centroid(&shape).unwrap();
+        assert!((centroid - vec2f(5.0 / 3.0, 5.0 / 3.0)).length() < 0.0001);
+
+        let sliver = [LineSegment2F::new(vec2f(0.0, 0.0), vec2f(3.0, 3.0)),
+                      LineSegment2F::new(vec2f(3.0, 3.0), vec2f(0.0, 0.0))];
+        assert_eq!(line_segment::area_centroid(&sliver), None);
+        assert_eq!(line_segment::area_centroid(&[]), None);
+    }
+
+    #[test]
+    fn test_split_into_tiles_seamless() {
+        let segment = LineSegment2F::new(vec2f(2.3, 30.7), vec2f(40.1, 4.9));
+        let pieces = segment.split_into_tiles_seamless(16.0);
+        let tiles: Vec<_> = pieces.iter().map(|&(x, y, _)| (x, y)).collect();
+        assert_eq!(tiles, vec![(0, 1), (1, 1), (1, 0), (2, 0)]);
+        assert_eq!(pieces[0].2.from(), segment.from());
+        assert_eq!(pieces[3].2.to(), segment.to());
+        for pair in pieces.windows(2) {
+            assert_eq!(pair[0].2.to(), pair[1].2.from());
+        }
+        assert_eq!(pieces[0].2.to().x(), 16.0);
+        assert_eq!(pieces[1].2.to().y(), 16.0);
+        assert_eq!(pieces[2].2.to().x(), 32.0);
+
+        // Through a tile corner, off by less than the epsilon.
+        let segment = LineSegment2F::new(vec2f(8.0, 8.0004), vec2f(24.0, 24.0));
+        let pieces = segment.split_into_tiles_seamless(16.0);
+        let tiles: Vec<_> = pieces.iter().map(|&(x, y, _)| (x, y)).collect();
+        assert_eq!(tiles, vec![(0, 0), (1, 1)]);
+        assert_eq!(pieces[0].2.to(), vec2f(16.0, 16.0));
+    }
+
+    #[test]
+    fn test_intersection_homogeneous() {
+        let a = LineSegment2F::new(vec2f(1.0, 1.0), vec2f(5.0, 3.0));
+        let b = LineSegment2F::new(vec2f(2.0, 4.0), vec2f(4.0, 0.0));
+        let point = a.intersection_homogeneous(b);
+        assert_eq!(point[3], 0.0);
+        let point = Vector2F(point.xy()) * (1.0 / point[2]);
+        assert!((point - a.sample(a.intersection_t(b).unwrap())).length() < 0.0001);
+        assert!((point - vec2f(3.0, 2.0)).length() < 0.0001);
+
+        let parallel = LineSegment2F::new(vec2f(0.0, 1.0), vec2f(1.0, 1.0));
+        let horizontal = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(1.0, 0.0));
+        assert_eq!(horizontal.intersection_homogeneous(parallel)[2], 0.0);
+    }
+
+    #[test]
+    fn test_accumulate_coverage_fixed() {
+        // Texels of `resources/textures/area-lut.png`.
+        let golden = [((0, 0), 255), ((120, 0), 255), ((127, 0), 143), ((128, 0), 128),
+                      ((136, 0), 0), ((255, 3), 0), ((131, 5), 80), ((140, 8), 0),
+                      ((130, 40), 115), ((125, 60), 140), ((133, 100), 115), ((100, 200), 163),
+                      ((110, 255), 146)];
+        for &((u, v), texel) in &golden {
+            assert_eq!(line_segment::area_lut_texel(u, v), texel as f32 / 255.0);
+        }
+
+        // A horizontal edge 1/32 below the center of the first pixel looks up texel (128, 0)
+        // exactly.
+        let mut coverage = vec![0; 3];
+        LineSegment2F::new(vec2f(1.0, 0.5 + 1.0 / 32.0), vec2f(0.0, 0.5 + 1.0 / 32.0))
+            .accumulate_coverage_fixed(&mut coverage, 0.0, 0.0, 12);
+        assert_eq!(coverage, vec![(128.0 / 255.0 * 4096.0f32).round() as i32, 0, 0]);
+
+        // Vertical edges and edges below the row's strip contribute nothing.
+        LineSegment2F::new(vec2f(1.5, 0.0), vec2f(1.5, 1.0))
+            .accumulate_coverage_fixed(&mut coverage, 0.0, 0.0, 12);
+        LineSegment2F::new(vec2f(3.0, 4.5), vec2f(0.0, 4.0))
+            .accumulate_coverage_fixed(&mut coverage, 0.0, 0.0, 12);
+        assert_eq!(coverage, vec![(128.0 / 255.0 * 4096.0f32).round() as i32, 0, 0]);
+
+        // A polygon with edges crossing x = 0 resolves to its area in each pixel without a prefix
+        // sum, to within the table's accuracy. `utils/area-lut` evaluates each texel half a texel
+        // from its center, which shifts edges by 1/32 of a pixel, on the GPU and here alike.
+        let quad = polygon_segments(&[vec2f(-2.0, 0.5), vec2f(5.0, 0.0), vec2f(6.0, 3.0),
+                                      vec2f(1.0, 3.5)]);
+        let expected = [
+            [0.678571, 0.75, 0.821429, 0.892857, 0.964286, 0.166667, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.0, 0.0],
+            [0.875, 1.0, 1.0, 1.0, 1.0, 0.833333, 0.0, 0.0],
+            [0.125, 0.45, 0.35, 0.25, 0.15, 0.05, 0.0, 0.0],
+        ];
+        for (row, expected) in expected.iter().enumerate() {
+            let mut coverage = vec![0; 8];
+            for segment in &quad {
+                segment.accumulate_coverage_fixed(&mut coverage, 0.0, row as f32, 12);
+            }
+            for (&coverage, &expected) in coverage.iter().zip(expected.iter()) {
+                assert!(f32::abs(i32::abs(coverage) as f32 / 4096.0 - expected) < 0.05);
+            }
+        }
+    }
+
+    #[test]
+    fn test_monotone_triangulator() {
+        let triangulate = |vertices: &[(Vector2F, bool)]| {
+            let mut triangulator = MonotoneTriangulator::new();
+            for &(point, is_left_chain) in vertices {
+                triangulator.push_vertex(point, is_left_chain);
+            }
+            triangulator.take_triangles()
+        };
+        let total_area = |triangles: &[[Vector2F; 3]]| {
+            triangles.iter().map(|triangle| {
+                let area = super::polygon_area(triangle);
+                assert!(f32::abs(area) > 0.0);
+                f32::abs(area)
+            }).sum::<f32>()
+        };
+
+        // Chains that alternate between every vertex.
+        let vertices = [(vec2f(3.0, 10.0), true), (vec2f(5.0, 9.0), false),
+                        (vec2f(1.0, 8.0), true), (vec2f(4.0, 7.0), false),
+                        (vec2f(2.0, 6.0), true), (vec2f(6.0, 4.0), false),
+                        (vec2f(0.0, 3.0), true), (vec2f(3.0, 0.0), true)];
+        let outline = [vec2f(3.0, 10.0), vec2f(1.0, 8.0), vec2f(2.0, 6.0), vec2f(0.0, 3.0),
+                       vec2f(3.0, 0.0), vec2f(6.0, 4.0), vec2f(4.0, 7.0), vec2f(5.0, 9.0)];
+        let triangles = triangulate(&vertices);
+        assert_eq!(triangles.len(), 6);
+        assert!(f32::abs(total_area(&triangles) - f32::abs(super::polygon_area(&outline))) <
+                0.0001);
+
+        // A left chain with a reflex vertex and a right chain of a single edge. The bottom vertex
+        // may be tagged with either chain.
+        let outline = [vec2f(0.0, 10.0), vec2f(-4.0, 8.0), vec2f(-1.0, 5.0), vec2f(-4.0, 2.0),
+                       vec2f(-3.0, 1.0), vec2f(0.0, 0.0)];
+        let expected_area = f32::abs(super::polygon_area(&outline));
+        for &bottom_is_left_chain in &[false, true] {
+            let mut vertices: Vec<_> = outline.iter().map(|&point| (point, true)).collect();
+            vertices.last_mut().unwrap().1 = bottom_is_left_chain;
+            let triangles = triangulate(&vertices);
+            assert_eq!(triangles.len(), 4);
+            assert!(f32::abs(total_area(&triangles) - expected_area) < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_min_visibility_coverage() {
+        let pixel = RectF::new(vec2f(2.0, 3.0), vec2f(1.0, 1.0));
+        let hairline = LineSegment2F::new(vec2f(2.0, 3.05), vec2f(3.0, 3.08));
+        let coverage = f32::abs(hairline.exact_pixel_coverage(pixel));
+        assert!(coverage > 0.9 && coverage < 1.0);
+        assert_eq!(hairline.min_visibility_coverage(pixel, 0.25), coverage);
+        assert_eq!(hairline.reversed().min_visibility_coverage(pixel, 0.25), coverage);
+
+        // Nearly vertical, so it covers almost none of the pixel's area.
+        let steep = LineSegment2F::new(vec2f(2.5, 2.0), vec2f(2.501, 5.0));
+        assert!(f32::abs(steep.exact_pixel_coverage(pixel)) < 0.25);
+        assert_eq!(steep.min_visibility_coverage(pixel, 0.25), 0.25);
+
+        // Its bounds overlap the pixel, but the segment passes outside it.
+        let outside = LineSegment2F::new(vec2f(1.0, 3.5), vec2f(2.5, 5.0));
+        assert!(outside.bounds().intersects(pixel));
+        assert_eq!(outside.min_visibility_coverage(pixel, 0.25), 0.0);
+
+        // Along the pixel's edge.
+        let edge = LineSegment2F::new(vec2f(3.0, 3.0), vec2f(3.0, 4.0));
+        assert_eq!(edge.min_visibility_coverage(pixel, 0.25), 0.0);
+    }
+
+    #[test]
+    fn test_is_vertex_redundant() {
+        let (prev, next) = (vec2f(0.0, 0.0), vec2f(4.0, 2.0));
+        assert!(line_segment::is_vertex_redundant(prev, vec2f(2.0, 1.0), next, 0.01));
+
+        // Near-collinear vertices are redundant only within the tolerance.
+        let mid = vec2f(2.0, 1.0) + vec2f(-1.0, 2.0) * (0.05 / f32::sqrt(5.0));
+        assert!(line_segment::is_vertex_redundant(prev, mid, next, 0.06));
+        assert!(!line_segment::is_vertex_redundant(prev, mid, next, 0.04));
+
+        // A collinear vertex past the end of the chord is a turn back, not redundant.
+        assert!(!line_segment::is_vertex_redundant(prev, vec2f(6.0, 3.0), next, 0.01));
+    }
+
+    #[test]
+    fn test_trim_to_distance_range() {
+        let segment = LineSegment2F::new(vec2f(1.0, 1.0), vec2f(4.0, 5.0));
+        let trimmed = segment.trim_to_distance_range(1.0, 3.5).unwrap();
+        assert_eq!(trimmed.from(), segment.point_at_distance(1.0));
+        assert_eq!(trimmed.to(), segment.point_at_distance(3.5));
+
+        // Distances beyond either end are clamped to the segment.
+        assert_eq!(segment.trim_to_distance_range(-2.0, 7.0), Some(segment));
+        assert_eq!(segment.trim_to_distance_range(2.0, 9.0).unwrap().to(), segment.to());
+
+        // Reversed and out-of-range windows don't overlap the segment.
+        assert!(segment.trim_to_distance_range(3.0, 1.0).is_none());
+        assert!(segment.trim_to_distance_range(-3.0, -1.0).is_none());
+        assert!(segment.trim_to_distance_range(5.5, 6.0).is_none());
+    }
+
+    #[test]
+    fn test_insert_into_hash() {
+        let mut grid = HashMap::new();
+        LineSegment2F::new(vec2f(1.0, 1.0), vec2f(5.0, 3.0)).insert_into_hash(2.0, 7, &mut grid);
+        LineSegment2F::new(vec2f(4.5, 3.5), vec2f(4.5, 3.9)).insert_into_hash(2.0, 8, &mut grid);
+        let mut cells: Vec<_> = grid.keys().cloned().collect();
+        cells.sort();
+        assert_eq!(cells, vec![(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+        assert_eq!(grid[&(0, 0)], vec![7]);
+        assert_eq!(grid[&(2, 1)], vec![7, 8]);
+
+        // Negative coordinates fall into negative cells.
+        let mut grid = HashMap::new();
+        LineSegment2F::new(vec2f(-0.5, 0.5), vec2f(0.5, 0.5)).insert_into_hash(1.0, 0, &mut grid);
+        assert!(grid.contains_key(&(-1, 0)) && grid.contains_key(&(0, 0)) && grid.len() == 2);
+    }
+
+    #[test]
+    fn test_fill_fan_triangle() {
+        let path = polygon_segments(&[vec2f(0.0, 0.0), vec2f(4.0, 0.0), vec2f(4.0, 4.0),
+                                      vec2f(0.0, 4.0)]);
+        for &anchor in &[vec2f(1.0, 2.0), vec2f(-3.0, 7.0)] {
+            // The triangles covering a point sum to its winding number, whatever the anchor.
+            let winding = |point: Vector2F| {
+                path.iter().map(|segment| {
+                    let ([a, b, c], winding) = segment.fill_fan_triangle(anchor);
+                    if winding != 0 && line_segment::point_in_triangle(point, a, b, c) {
+                        winding
+                    } else {
+                        0
+                    }
+                }).sum::<i32>()
+            };
+            assert_eq!(winding(vec2f(2.5, 1.3)), 1);
+            assert_eq!(winding(vec2f(5.5, 1.3)), 0);
+        }
+
+        let segment = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(4.0, 0.0));
+        assert_eq!(segment.fill_fan_triangle(vec2f(2.0, 1.0)).1, 1);
+        assert_eq!(segment.fill_fan_triangle(vec2f(2.0, -1.0)).1, -1);
+        assert_eq!(segment.fill_fan_triangle(vec2f(6.0, 0.0)),
+                   ([vec2f(6.0, 0.0), vec2f(0.0, 0.0), vec2f(4.0, 0.0)], 0));
+    }
+
+    #[test]
+    fn test_subpixel_coverage_events() {
+        // An L-shaped path whose vertical edges span each row, where sampling the crossings at
+        // the scanline agrees with integrating the row's area.
+        let path = polygon_segments(&[vec2f(0.5, 0.0), vec2f(6.25, 0.0), vec2f(6.25, 2.0),
+                                      vec2f(3.75, 2.0), vec2f(3.75, 4.0), vec2f(0.5, 4.0)]);
+        for row in 0..4 {
+            let (mut events, mut expected) = (vec![0.0; 8], vec![0.0; 8]);
+            for segment in &path {
+                segment.accumulate_into_scanline(row as f32, &mut expected, 0.0);
+                if let Some((x, fraction, winding)) =
+                        segment.subpixel_coverage_events(row as f32 + 0.5) {
+                    let column = x.floor() as usize;
+                    events[column] += fraction * winding as f32;
+                    events[column + 1] += (1.0 - fraction) * winding as f32;
+                }
+            }
+            for (&events, &expected) in running_sum(&events).iter()
+                                                            .zip(running_sum(&expected).iter()) {
+                assert!(util::approx_eq(events, expected));
+            }
+        }
+
+        // A scanline through a shared vertex is counted by one segment only.
+        let (a, b) = (LineSegment2F::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0)),
+                      LineSegment2F::new(vec2f(1.0, 1.0), vec2f(0.0, 2.0)));
+        assert!(a.subpixel_coverage_events(1.0).is_none());
+        assert_eq!(b.subpixel_coverage_events(1.0), Some((1.0, 1.0, 1)));
+        assert!(a.subpixel_coverage_events(-0.5).is_none());
+    }
+
+    #[test]
+    fn test_reflect_through_point() {
+        let segment = LineSegment2F::new(vec2f(1.0, 2.0), vec2f(4.0, -1.0));
+        let center = vec2f(2.0, 0.5);
+        let reflected = segment.reflect_through_point(center);
+        assert_eq!(reflected, LineSegment2F::new(vec2f(3.0, -1.0), vec2f(0.0, 2.0)));
+        assert_eq!(reflected.midpoint() + segment.midpoint(), center * 2.0);
+        assert_eq!(reflected.reflect_through_point(center), segment);
+    }
+
+    #[test]
+    fn test_radial_spokes() {
+        let center = vec2f(3.0, -2.0);
+        let spokes = line_segment::radial_spokes(center, 1.0, 4.0, 8, 0.25 * PI);
+        assert_eq!(spokes.len(), 8);
+        for (index, spoke) in spokes.iter().enumerate() {
+            assert!(util::approx_eq((spoke.from() - center).length(), 1.0));
+            assert!(util::approx_eq((spoke.to() - center).length(), 4.0));
+            let angle = 0.25 * PI + index as f32 * 0.25 * PI;
+            assert!((spoke.direction() - vec2f(angle.cos(), angle.sin())).length() < 0.0001);
+        }
+        assert!(line_segment::radial_spokes(center, 1.0, 4.0, 0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_sdf_gradient() {
+        let segment = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(4.0, 0.0));
+        assert_eq!(segment.sdf_gradient(vec2f(2.0, 3.0)), vec2f(0.0, 1.0));
+        assert_eq!(segment.sdf_gradient(vec2f(2.0, -0.5)), vec2f(0.0, -1.0));
+
+        // Beyond the ends, the gradient points away from the nearest endpoint.
+        let gradient = segment.sdf_gradient(vec2f(7.0, 4.0));
+        assert!((gradient - vec2f(0.6, 0.8)).length() < 0.0001);
+        assert_eq!(segment.sdf_gradient(vec2f(1.0, 0.0)), Vector2F::zero());
+    }
+
+    #[test]
+    fn test_soft_coverage() {
+        let segment = LineSegment2F::new(vec2f(0.0, 0.0), vec2f(4.0, 0.0));
+        assert_eq!(segment.soft_coverage(vec2f(2.0, 0.5), 1.0, 1.0), 1.0);
+        assert!(util::approx_eq(segment.soft_coverage(vec2f(2.0, 1.5), 1.0, 1.0), 0.5));
+        assert!(util::approx_eq(segment.soft_coverage(vec2f(-1.25, 0.0), 1.0, 0.5), 0.5));
+        assert_eq!(segment.soft_coverage(vec2f(2.0, 2.5), 1.0, 1.0), 0.0);
+
+        // Without softness, the edge is hard.
+        assert_eq!(segment.soft_coverage(vec2f(2.0, 1.0), 1.0, 0.0), 1.0);
+        assert_eq!(segment.soft_coverage(vec2f(2.0, 1.01), 1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_vertex_convexity() {
+        // An L shape with positive signed area, with one reflex vertex at (2, 2).
+        let points = [vec2f(0.0, 0.0), vec2f(4.0, 0.0), vec2f(4.0, 2.0), vec2f(2.0, 2.0),
+                      vec2f(2.0, 4.0), vec2f(0.0, 4.0)];
+        let edges = polygon_segments(&points);
+        assert!(line_segment::signed_area(&edges) > 0.0);
+        let convex: Vec<_> = (0..edges.len()).map(|index| {
+            line_segment::vertex_convexity(edges[index], edges[(index + 1) % edges.len()], true)
+        }).collect();
+        assert_eq!(convex, vec![true, true, false, true, true, true]);
+
+        // Reversing the polygon flips its winding, but not which vertices are convex.
+        let reversed: Vec<_> = edges.iter().rev().map(|edge| edge.reversed()).collect();
+        assert_eq!(reversed[2].to(), vec2f(2.0, 2.0));
+        assert!(!line_segment::vertex_convexity(reversed[2], reversed[3], false));
+        assert!(line_segment::vertex_convexity(reversed[2], reversed[3], true));
+        assert!(line_segment::vertex_convexity(reversed[0], reversed[1], false));
+
+        // Collinear edges are neither.
+        let (a, b) = (LineSegment2F::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0)),
+                      LineSegment2F::new(vec2f(1.0, 1.0), vec2f(2.0, 2.0)));
+        assert!(!line_segment::vertex_convexity(a, b, true));
+        assert!(!line_segment::vertex_convexity(a, b, false));
+    }
+
+    #[test]
+    fn test_estimate_tangents() {
+        let points = [vec2f(0.0, 0.0), vec2f(2.0, 0.0), vec2f(2.0, 2.0)];
+        let tangents = line_segment::estimate_tangents(&points, false);
+        assert_eq!(tangents[0], vec2f(1.0, 0.0));
+        assert!((tangents[1] - vec2f(1.0, 1.0).normalize()).length() < 0.0001);
+        assert_eq!(tangents[2], vec2f(0.0, 1.0));
+
+        // Closing the polyline averages in the wraparound segment at both ends.
+        let tangents = line_segment::estimate_tangents(&points, true);
+        let back = vec2f(-1.0, -1.0).normalize();
+        assert!((tangents[0] - (vec2f(1.0, 0.0) + back).normalize()).length() < 0.0001);
+        assert!((tangents[2] - (vec2f(0.0, 1.0) + back).normalize()).length() < 0.0001);
+
+        // Where the polyline doubles back, the leaving direction wins.
+        let tangents = line_segment::estimate_tangents(&[vec2f(0.0, 0.0), vec2f(3.0, 0.0),
+                                                         vec2f(1.0, 0.0)], false);
+        assert_eq!(tangents[1], vec2f(-1.0, 0.0));
+        assert!(line_segment::estimate_tangents(&[], false).is_empty());
+    }
+
+    #[test]
+    fn test_intersect_axis_aligned_lines() {
+        let segment = LineSegment2F::new(vec2f(1.0, 1.0), vec2f(5.0, 3.0));
+        assert_eq!(segment.intersect_horizontal_line(2.0), Some(vec2f(3.0, 2.0)));
+        assert_eq!(segment.intersect_vertical_line(2.0), Some(vec2f(2.0, 1.5)));
+        assert!(segment.intersect_horizontal_line(3.5).is_none());
+        assert!(segment.intersect_vertical_line(0.5).is_none());
+
+        // Endpoints on the line count as crossings.
+        assert_eq!(segment.intersect_horizontal_line(3.0), Some(vec2f(5.0, 3.0)));
+        assert_eq!(segment.intersect_vertical_line(1.0), Some(vec2f(1.0, 1.0)));
+
+        // Parallel lines never cross, even when the segment lies on them.
+        let horizontal = LineSegment2F::new(vec2f(0.0, 2.0), vec2f(4.0, 2.0));
+        assert!(horizontal.intersect_horizontal_line(2.0).is_none());
+        assert_eq!(horizontal.intersect_vertical_line(1.0), Some(vec2f(1.0, 2.0)));
+        let vertical = LineSegment2F::new(vec2f(3.0, 0.0), vec2f(3.0, 4.0));
+        assert!(vertical.intersect_vertical_line(3.0).is_none());
+        assert_eq!(vertical.intersect_horizontal_line(1.0), Some(vec2f(3.0, 1.0)));
+    }
+
+    #[test]
+    fn test_to_x_monotone() {
+        let segment = LineSegment2F::new(vec2f(5.0, 1.0), vec2f(2.0, 3.0));
+        let monotone = segment.to_x_monotone();
+        assert_eq!(monotone, segment.reversed());
+        assert_eq!(monotone.y_winding(), -segment.y_winding());
+        assert_eq!(monotone.to_x_monotone(), monotone);
+
+        // Vertical segments are oriented downward, from smaller to larger y.
+        let vertical = LineSegment2F::new(vec2f(2.0, 4.0), vec2f(2.0, 1.0));
+        assert_eq!(vertical.to_x_monotone(), LineSegment2F::new(vec2f(2.0, 1.0), vec2f(2.0, 4.0)));
+        assert_eq!(vertical.reversed().to_x_monotone(), vertical.reversed());
+    }
+
+    #[test]
+    fn test_to_sweep_events() {
+        let segment = LineSegment2F::new(vec2f(5.0, 1.0), vec2f(2.0, 3.0));
+        let (left, right) = segment.to_sweep_events(3);
+        assert!(left.is_left && !right.is_left);
+        assert_eq!((left.point, left.other_point), (vec2f(2.0, 3.0), vec2f(5.0, 1.0)));
+        assert_eq!((right.point, right.other_point), (vec2f(5.0, 1.0), vec2f(2.0, 3.0)));
+        assert_eq!((left.polygon_id, right.polygon_id), (3, 3));
+        assert_eq!(left.segment(), segment.to_x_monotone());
+        assert_eq!(right.segment(), segment.to_x_monotone());
+
+        // With equal x, the endpoint with smaller y comes first.
+        let vertical = LineSegment2F::new(vec2f(2.0, 4.0), vec2f(2.0, 1.0));
+        let (left, right) = vertical.to_sweep_events(0);
+        assert_eq!((left.point, right.point), (vec2f(2.0, 1.0), vec2f(2.0, 4.0)));
+        assert_eq!(vertical.reversed().to_sweep_events(0), (left, right));
+    }
 }
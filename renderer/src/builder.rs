@@ -21,17 +21,16 @@ use crate::paint::{PaintInfo, PaintMetadata};
 use crate::scene::{DisplayItem, Scene};
 use crate::tile_map::DenseTileMap;
 use crate::tiler::Tiler;
-use crate::tiles::{self, DrawTilingPathInfo, PackedTile, TILE_HEIGHT, TILE_WIDTH, TilingPathInfo};
+use crate::tiles::{self, DrawTilingPathInfo, PackedTile, TILE_WIDTH, TilingPathInfo};
 use crate::z_buffer::{DepthMetadata, ZBuffer};
 use pathfinder_content::effects::{BlendMode, Filter};
 use pathfinder_content::fill::FillRule;
 use pathfinder_content::render_target::RenderTargetId;
-use pathfinder_geometry::line_segment::{LineSegment2F, LineSegmentU4, LineSegmentU8};
+use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::{Vector2I, vec2i};
 use pathfinder_gpu::TextureSamplingFlags;
-use pathfinder_simd::default::{F32x4, I32x4};
 use std::sync::atomic::AtomicUsize;
 use instant::Instant;
 use std::u32;
@@ -612,46 +611,24 @@ impl ObjectBuilder {
             return;
         }
 
-        debug_assert_eq!(TILE_WIDTH, TILE_HEIGHT);
-
         // Compute the upper left corner of the tile.
-        let tile_size = F32x4::splat(TILE_WIDTH as f32);
-        let tile_upper_left = tile_coords.to_f32().0.to_f32x4().xyxy() * tile_size;
-
-        // Convert to 4.8 fixed point.
-        let segment = (segment.0 - tile_upper_left) * F32x4::splat(256.0);
-        let (min, max) = (F32x4::default(), F32x4::splat((TILE_WIDTH * 256 - 1) as f32));
-        let segment = segment.clamp(min, max).to_i32x4();
-        let (from_x, from_y, to_x, to_y) = (segment[0], segment[1], segment[2], segment[3]);
-
-        // Cull degenerate fills.
-        if from_x == to_x {
-            debug!("... culling!");
-            return;
-        }
+        let tile_origin = tile_coords.to_f32() * TILE_WIDTH as f32;
+
+        // Pack instance data, culling degenerate fills.
+        let mut fill = match Fill::from_line_segment(segment, tile_origin, 0) {
+            Some(fill) => fill,
+            None => {
+                debug!("... culling!");
+                return;
+            }
+        };
 
         // Allocate a global tile if necessary.
         let alpha_tile_id = self.get_or_allocate_alpha_tile_index(scene_builder, tile_coords);
+        fill.alpha_tile_index = alpha_tile_id.tile();
 
-        // Pack whole pixels.
-        let px = (segment & I32x4::splat(0xf00)).to_u32x4();
-        let px = (px >> 8).to_i32x4() | (px >> 4).to_i32x4().yxwz();
-
-        // Pack instance data.
         debug!("... OK, pushing");
-        self.fills.push(FillBatchEntry {
-            page: alpha_tile_id.page(),
-            fill: Fill {
-                px: LineSegmentU4 { from: px[0] as u8, to: px[2] as u8 },
-                subpx: LineSegmentU8 {
-                    from_x: from_x as u8,
-                    from_y: from_y as u8,
-                    to_x:   to_x   as u8,
-                    to_y:   to_y   as u8,
-                },
-                alpha_tile_index: alpha_tile_id.tile(),
-            },
-        });
+        self.fills.push(FillBatchEntry { page: alpha_tile_id.page(), fill });
     }
 
     fn get_or_allocate_alpha_tile_index(
@@ -13,14 +13,16 @@
 use crate::builder::{ALPHA_TILES_PER_LEVEL, ALPHA_TILE_LEVEL_COUNT};
 use crate::options::BoundingQuad;
 use crate::paint::PaintCompositeOp;
+use crate::tiles::{TILE_HEIGHT, TILE_WIDTH};
 use pathfinder_color::ColorU;
 use pathfinder_content::effects::{BlendMode, Filter};
 use pathfinder_content::render_target::RenderTargetId;
-use pathfinder_geometry::line_segment::{LineSegmentU4, LineSegmentU8};
+use pathfinder_geometry::line_segment::{LineSegment2F, LineSegmentU4, LineSegmentU8};
 use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::transform2d::Transform2F;
-use pathfinder_geometry::vector::Vector2I;
+use pathfinder_geometry::vector::{Vector2F, Vector2I};
 use pathfinder_gpu::TextureSamplingFlags;
+use pathfinder_simd::default::{F32x4, I32x4};
 use std::fmt::{Debug, Formatter, Result as DebugResult};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -233,6 +235,65 @@ impl AlphaTileId {
     }
 }
 
+impl Fill {
+    /// Packs `segment` into the layout the fill shader reads, relative to the tile whose upper
+    /// left corner is at `tile_origin`.
+    ///
+    /// The endpoints are clamped to the tile and converted to 4.8 fixed point: `px` holds the
+    /// whole pixels and `subpx` the fractional parts. Returns `None` if the packed segment has no
+    /// horizontal extent, since the shader would accumulate no coverage for it.
+    pub fn from_line_segment(segment: LineSegment2F,
+                             tile_origin: Vector2F,
+                             alpha_tile_index: u16)
+                             -> Option<Fill> {
+        debug_assert_eq!(TILE_WIDTH, TILE_HEIGHT);
+
+        // Convert to 4.8 fixed point.
+        let segment = (segment.0 - tile_origin.0.to_f32x4().xyxy()) * F32x4::splat(256.0);
+        let (min, max) = (F32x4::default(), F32x4::splat((TILE_WIDTH * 256 - 1) as f32));
+        let segment = segment.clamp(min, max).to_i32x4();
+        let (from_x, from_y, to_x, to_y) = (segment[0], segment[1], segment[2], segment[3]);
+
+        // Cull degenerate fills.
+        if from_x == to_x {
+            return None;
+        }
+
+        // Pack whole pixels.
+        let px = (segment & I32x4::splat(0xf00)).to_u32x4();
+        let px = (px >> 8).to_i32x4() | (px >> 4).to_i32x4().yxwz();
+
+        Some(Fill {
+            px: LineSegmentU4 { from: px[0] as u8, to: px[2] as u8 },
+            subpx: LineSegmentU8 {
+                from_x: from_x as u8,
+                from_y: from_y as u8,
+                to_x:   to_x   as u8,
+                to_y:   to_y   as u8,
+            },
+            alpha_tile_index,
+        })
+    }
+}
+
+impl FillBatchEntry {
+    /// Packs `segment` into a fill for the given alpha tile, ready to be sent to the GPU with
+    /// `RenderCommand::AddFills`. See `Fill::from_line_segment()`.
+    ///
+    /// This is a constructor here rather than a `LineSegment2F::to_fill_batch_entry()` method
+    /// because `pathfinder_geometry` can't depend on the renderer's types. It returns `None` for
+    /// fills that the builder would cull, so that callers pushing fills directly don't send
+    /// the GPU work that accumulates nothing.
+    #[inline]
+    pub fn from_line_segment(segment: LineSegment2F,
+                             tile_origin: Vector2F,
+                             alpha_tile_id: AlphaTileId)
+                             -> Option<FillBatchEntry> {
+        let fill = Fill::from_line_segment(segment, tile_origin, alpha_tile_id.tile())?;
+        Some(FillBatchEntry { fill, page: alpha_tile_id.page() })
+    }
+}
+
 impl Debug for RenderCommand {
     fn fmt(&self, formatter: &mut Formatter) -> DebugResult {
         match *self {
@@ -274,3 +335,46 @@ impl Debug for RenderCommand {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pathfinder_geometry::line_segment::LineSegment2F;
+    use pathfinder_geometry::vector::vec2f;
+
+    use super::{AlphaTileId, Fill, FillBatchEntry};
+
+    #[test]
+    fn test_fill_from_line_segment() {
+        let tile_origin = vec2f(16.0, 32.0);
+
+        // Tile-local (1.5, 3.25) to (14.75, 8.125).
+        let segment = LineSegment2F::new(vec2f(17.5, 35.25), vec2f(30.75, 40.125));
+        let fill = Fill::from_line_segment(segment, tile_origin, 3).unwrap();
+        assert_eq!((fill.px.from, fill.px.to), (0x31, 0x8e));
+        assert_eq!((fill.subpx.from_x, fill.subpx.from_y, fill.subpx.to_x, fill.subpx.to_y),
+                   (0x80, 0x40, 0xc0, 0x20));
+        assert_eq!(fill.alpha_tile_index, 3);
+
+        // Fills with no horizontal extent, including those clamped to the tile's left edge, are
+        // culled.
+        let vertical = LineSegment2F::new(vec2f(21.0, 33.0), vec2f(21.0, 47.0));
+        assert!(Fill::from_line_segment(vertical, tile_origin, 0).is_none());
+        let left = LineSegment2F::new(vec2f(10.0, 33.0), vec2f(12.0, 47.0));
+        assert!(Fill::from_line_segment(left, tile_origin, 0).is_none());
+
+        // Endpoints outside the tile are clamped to its last subpixel.
+        let crossing = LineSegment2F::new(vec2f(14.0, 36.0), vec2f(36.0, 36.0));
+        let fill = Fill::from_line_segment(crossing, tile_origin, 0).unwrap();
+        assert_eq!((fill.px.from, fill.px.to), (0x40, 0x4f));
+        assert_eq!((fill.subpx.from_x, fill.subpx.to_x), (0x00, 0xff));
+        let below = LineSegment2F::new(vec2f(18.0, 50.0), vec2f(20.0, 52.0));
+        let fill = Fill::from_line_segment(below, tile_origin, 0).unwrap();
+        assert_eq!((fill.px.from, fill.px.to), (0xf2, 0xf4));
+        assert_eq!((fill.subpx.from_y, fill.subpx.to_y), (0xff, 0xff));
+
+        let alpha_tile_id = AlphaTileId((2 << 16) | 5);
+        let entry = FillBatchEntry::from_line_segment(segment, tile_origin, alpha_tile_id).unwrap();
+        assert_eq!((entry.page, entry.fill.alpha_tile_index), (2, 5));
+        assert_eq!((entry.fill.px.from, entry.fill.px.to), (0x31, 0x8e));
+    }
+}